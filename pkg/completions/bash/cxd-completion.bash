@@ -8,16 +8,49 @@ _cxd() {
         LAST_WORD="${COMP_WORDS[$COMP_CWORD - 1]}"
     fi
 
-    # All operations must have an _op_<OP_CHAR>() function defined
-    local OPERATIONS="--add -a --remove -r --list -l --clear"
+    # All operations must have an _cxd_op_<NAME>() function defined, NAME being the long name
+    local OPERATIONS="--add -a --add-step --remove -r --list -l --clear --edit --export --import --trust --untrust --history --again --deps --pick --tag --untag --run-tag --rename --copy --show"
     local GLOBAL_OPTIONS="--file -f --help -h --version"
     local GLOBAL_SKIPS="--file 1 -f 1 --help 0 -h 0 --version 0"
 
-    # Calls cxd to get a list of valid command names
+    # Calls cxd to get a list of valid command names, quoted for compgen -W
     _cxd_names() {
-        # Doing some unsplitting to replace newlines with spaces
-        local names=($($CXD --list --short))
-        echo "${names[@]}"
+        # NUL delimited, so names may contain spaces
+        local name
+        while IFS= read -r -d '' name; do
+            printf '%q ' "$name"
+        done < <($CXD --list --format null)
+    }
+
+    # Calls cxd to get a list of tags in use, quoted for compgen -W
+    _cxd_tags() {
+        # The tsv tags field, shell quoted words which never contain whitespace
+        $CXD --list --format tsv | awk -F '\t' '{ print $9 }' | tr ' ' '\n' | sort -u | tr '\n' ' '
+    }
+
+    # Calls cxd to find the program a named command executes, nothing for shell scripts
+    # Usage: _cxd_program name
+    _cxd_program() {
+        $CXD --list | awk -v name="$1" '
+            /^\[([0-9]+|-)\]: / { found = (substr($0, index($0, ": ") + 2) == name) }
+            found && /^  cmd: / { print $2; exit }
+        '
+    }
+
+    # Execution, completes pass-through args with the underlying program's completion
+    _cxd_exec() {
+        local program
+        program=$(_cxd_program "${COMP_WORDS[$FIRST_ARG_INDEX]}")
+        if [ -z "$program" ]; then
+            _cxd_reply -f
+            return
+        fi
+        # Rewriting the line as if the program was invoked in place of <NAME>
+        COMP_WORDS[$FIRST_ARG_INDEX]="$program"
+        local before=("${COMP_WORDS[@]:0:$((COMP_CWORD + 1))}")
+        COMP_LINE="${before[*]}"
+        COMP_POINT=${#COMP_LINE}
+        _command_offset $FIRST_ARG_INDEX
     }
 
     # Counts number of free args in ${COMP_WORDS} after start_index
@@ -57,58 +90,277 @@ _cxd() {
     }
 
     # Add operation
-    _cxd_op_a() {
-        local ADD_OPTIONS="--cwd -c --dir -d --env -e"
+    _cxd_op_add() {
+        local ADD_OPTIONS="--cwd -c --dir -d --env -e --dep --tag --desc --local -L --scope --shell"
+        local SHELLS="ash bash dash fish ksh mksh sh zsh"
+        # A bare --shell only takes a known shell as its value, anything else is <NAME>
+        local shell_skip=0 i
+        for ((i = OP_LOC + 1; i < COMP_CWORD - 1; i++)); do
+            if [ "${COMP_WORDS[$i]}" = "--shell" ] && [[ " $SHELLS " == *" ${COMP_WORDS[$((i + 1))]} "* ]]; then
+                shell_skip=1
+            fi
+        done
+        _cxd_count_free_args $((OP_LOC + 1)) --dir 1 -d 1 --env 3 -e 3 --dep 1 --tag 1 --desc 1 --scope 1 --shell $shell_skip
+        case $FREE_ARGS in
+            0|1) 
+                if [ "$LAST_WORD" = "--shell" ]; then
+                    _cxd_reply -W "$SHELLS $ADD_OPTIONS $GLOBAL_OPTIONS"
+                elif [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+                    _cxd_reply -f
+                elif [ "$LAST_WORD" = "--env" ] || [ "$LAST_WORD" = "-e" ] || [ "$LAST_WORD" = "--desc" ]; then
+                    COMPREPLY=()
+                elif [ "$LAST_WORD" = "--dep" ]; then
+                    _cxd_reply -W "$(_cxd_names)"
+                elif [ "$LAST_WORD" = "--tag" ]; then
+                    _cxd_reply -W "$(_cxd_tags)"
+                elif [ "$LAST_WORD" = "--dir" ] || [ "$LAST_WORD" = "-d" ] || [ "$LAST_WORD" = "--scope" ]; then
+                    _cxd_reply -d
+                else
+                    _cxd_reply -W "$ADD_OPTIONS $GLOBAL_OPTIONS"
+                fi
+                ;;
+            2) _cxd_reply -abc ;;
+            *) _command_offset $((FIRST_ARG_INDEX + 1)) ;;
+        esac
+    }
+
+    # Add step operation
+    _cxd_op_add-step() {
+        local ADD_STEP_OPTIONS="--cwd -c --dir -d --env -e"
         _cxd_count_free_args $((OP_LOC + 1)) --dir 1 -d 1 --env 3 -e 3
         case $FREE_ARGS in
             0|1) 
                 if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
-                    COMPREPLY=($(_cxd_compgen -f))
+                    _cxd_reply -f
                 elif [ "$LAST_WORD" = "--env" ] || [ "$LAST_WORD" = "-e" ]; then
                     COMPREPLY=()
                 elif [ "$LAST_WORD" = "--dir" ] || [ "$LAST_WORD" = "-d" ]; then
-                    COMPREPLY=($(_cxd_compgen -d))
+                    _cxd_reply -d
                 else
-                    COMPREPLY=($(_cxd_compgen -W "$ADD_OPTIONS $GLOBAL_OPTIONS")) 
+                    _cxd_reply -W "$ADD_STEP_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
                 fi
                 ;;
-            2) COMPREPLY=($(_cxd_compgen -abc)) ;;
+            2) _cxd_reply -abc ;;
             *) _command_offset $((FIRST_ARG_INDEX + 1)) ;;
         esac
     }
 
     # Remove operation
-    _cxd_op_r() {
+    _cxd_op_remove() {
         local REMOVE_OPTIONS="--id -i"
         if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
-            COMPREPLY=($(_cxd_compgen -f))
+            _cxd_reply -f
         elif [ "$LAST_WORD" = "--id" ] || [ "$LAST_WORD" = "-i" ]; then
             COMPREPLY=()
         else
-            COMPREPLY=($(_cxd_compgen -W "$REMOVE_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"))
+            _cxd_reply -W "$REMOVE_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
         fi
     }
 
     # List operation
-    _cxd_op_l() {
-        local LIST_OPTIONS="--short -s"
+    _cxd_op_list() {
+        local LIST_OPTIONS="--short -s --format --grep --here --dir -d --env -e --tag"
         if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
-            COMPREPLY=($(_cxd_compgen -f))
+            _cxd_reply -f
+        elif [ "$LAST_WORD" = "--format" ]; then
+            _cxd_reply -W "json tsv null"
+        elif [ "$LAST_WORD" = "--dir" ] || [ "$LAST_WORD" = "-d" ]; then
+            _cxd_reply -d
+        elif [ "$LAST_WORD" = "--tag" ]; then
+            _cxd_reply -W "$(_cxd_tags)"
+        elif [ "$LAST_WORD" = "--grep" ] || [ "$LAST_WORD" = "--env" ] || [ "$LAST_WORD" = "-e" ]; then
+            COMPREPLY=()
         else
-            COMPREPLY=($(_cxd_compgen -W "$LIST_OPTIONS $GLOBAL_OPTIONS"))
+            _cxd_reply -W "$LIST_OPTIONS $GLOBAL_OPTIONS"
         fi
     }
 
     # Clear operation
-    _cxd_op_c() {
+    _cxd_op_clear() {
         local CLEAR_OPTIONS=""
         if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
-            COMPREPLY=($(_cxd_compgen -f))
+            _cxd_reply -f
+        else
+            _cxd_reply -W "$CLEAR_OPTIONS $GLOBAL_OPTIONS"
+        fi
+    }
+
+    # Edit operation
+    _cxd_op_edit() {
+        local EDIT_OPTIONS="--set-cmd --set-dir --set-desc --add-env --unset-env --add-dep --remove-dep --set-args --id -i"
+        _cxd_count_free_args $((OP_LOC + 1)) --file 1 -f 1 --set-cmd 1 --set-dir 1 --set-desc 1 --add-env 1 --unset-env 1 --add-dep 1 --remove-dep 1
+        local i
+        for ((i = OP_LOC + 1; i < COMP_CWORD; i++)); do
+            if [ "${COMP_WORDS[$i]}" = "--set-args" ]; then
+                _cxd_reply -f
+                return
+            fi
+        done
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        elif [ "$LAST_WORD" = "--set-dir" ]; then
+            _cxd_reply -d
+        elif [ "$LAST_WORD" = "--set-cmd" ]; then
+            _cxd_reply -c
+        elif [ "$LAST_WORD" = "--set-desc" ] || [ "$LAST_WORD" = "--add-env" ] || [ "$LAST_WORD" = "--unset-env" ]; then
+            COMPREPLY=()
+        elif [ "$LAST_WORD" = "--add-dep" ] || [ "$LAST_WORD" = "--remove-dep" ]; then
+            _cxd_reply -W "$(_cxd_names)"
+        elif [ $FREE_ARGS -eq 0 ] || [ $FIRST_ARG_INDEX -eq $COMP_CWORD ]; then
+            _cxd_reply -W "$EDIT_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
+        else
+            _cxd_reply -W "$EDIT_OPTIONS $GLOBAL_OPTIONS"
+        fi
+    }
+
+    # Export operation
+    _cxd_op_export() {
+        local EXPORT_OPTIONS="--format --id -i"
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        elif [ "$LAST_WORD" = "--format" ]; then
+            _cxd_reply -W "json toml"
+        else
+            _cxd_reply -W "$EXPORT_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
+        fi
+    }
+
+    # Import operation
+    _cxd_op_import() {
+        local IMPORT_OPTIONS="--format --skip-existing --overwrite --rename-suffix --dry-run"
+        if [ "$LAST_WORD" = "--format" ]; then
+            _cxd_reply -W "json toml"
+        elif [ "$LAST_WORD" = "--rename-suffix" ]; then
+            COMPREPLY=()
+        elif [[ "$WORD" == -* ]]; then
+            _cxd_reply -W "$IMPORT_OPTIONS $GLOBAL_OPTIONS"
+        else
+            _cxd_reply -f
+        fi
+    }
+
+    # Trust operation
+    _cxd_op_trust() {
+        if [[ "$WORD" == -* ]]; then
+            _cxd_reply -W "$GLOBAL_OPTIONS"
+        else
+            _cxd_reply -f
+        fi
+    }
+
+    # Untrust operation
+    _cxd_op_untrust() {
+        _cxd_op_trust
+    }
+
+    # History operation
+    _cxd_op_history() {
+        local HISTORY_OPTIONS="--failed --since --until"
+        if [ "$LAST_WORD" = "--since" ] || [ "$LAST_WORD" = "--until" ]; then
+            COMPREPLY=()
+        else
+            _cxd_reply -W "$HISTORY_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
+        fi
+    }
+
+    # Again operation
+    _cxd_op_again() {
+        local AGAIN_OPTIONS="--failed --anywhere --record --keep-going --dry-run --print-shell"
+        _cxd_reply -W "$AGAIN_OPTIONS $GLOBAL_OPTIONS"
+    }
+
+    # Deps operation
+    _cxd_op_deps() {
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        else
+            _cxd_reply -W "$GLOBAL_OPTIONS $(_cxd_names)"
+        fi
+    }
+
+    # Pick operation
+    _cxd_op_pick() {
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        else
+            _cxd_reply -W "$GLOBAL_OPTIONS"
+        fi
+    }
+
+    # Tag operation, a name then its tags
+    _cxd_op_tag() {
+        _cxd_count_free_args $((OP_LOC + 1)) --file 1 -f 1
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        elif [ $FREE_ARGS -eq 0 ] || [ $FIRST_ARG_INDEX -eq $COMP_CWORD ]; then
+            _cxd_reply -W "$GLOBAL_OPTIONS $(_cxd_names)"
+        else
+            _cxd_reply -W "$(_cxd_tags)"
+        fi
+    }
+
+    # Untag operation
+    _cxd_op_untag() {
+        _cxd_op_tag
+    }
+
+    # Run tag operation
+    _cxd_op_run-tag() {
+        local RUN_TAG_OPTIONS="--record --keep-going --dry-run --print-shell"
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        else
+            _cxd_reply -W "$RUN_TAG_OPTIONS $GLOBAL_OPTIONS $(_cxd_tags)"
+        fi
+    }
+
+    # Rename operation, an existing name then a new one
+    _cxd_op_rename() {
+        _cxd_count_free_args $((OP_LOC + 1)) --file 1 -f 1
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        elif [ $FREE_ARGS -eq 0 ] || [ $FIRST_ARG_INDEX -eq $COMP_CWORD ]; then
+            _cxd_reply -W "$GLOBAL_OPTIONS $(_cxd_names)"
         else
-            COMPREPLY=($(_cxd_compgen -W "$CLEAR_OPTIONS $GLOBAL_OPTIONS"))
+            COMPREPLY=()
         fi
     }
 
+    # Copy operation, an existing name then a new one
+    _cxd_op_copy() {
+        local COPY_OPTIONS="--dir -d"
+        _cxd_count_free_args $((OP_LOC + 1)) --file 1 -f 1 --dir 1 -d 1
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        elif [ "$LAST_WORD" = "--dir" ] || [ "$LAST_WORD" = "-d" ]; then
+            _cxd_reply -d
+        elif [ $FREE_ARGS -eq 0 ] || [ $FIRST_ARG_INDEX -eq $COMP_CWORD ]; then
+            _cxd_reply -W "$COPY_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
+        else
+            _cxd_reply -W "$COPY_OPTIONS"
+        fi
+    }
+
+    # Show operation
+    _cxd_op_show() {
+        local SHOW_OPTIONS="--id -i"
+        if [ "$LAST_WORD" = "--file" ] || [ "$LAST_WORD" = "-f" ]; then
+            _cxd_reply -f
+        else
+            _cxd_reply -W "$SHOW_OPTIONS $GLOBAL_OPTIONS $(_cxd_names)"
+        fi
+    }
+
+    # Sets COMPREPLY to the matches of _cxd_compgen, escaped so words with spaces stay whole
+    # Usage: _cxd_reply [compgen args]...
+    _cxd_reply() {
+        local match
+        COMPREPLY=()
+        while IFS= read -r match; do
+            COMPREPLY+=("$(printf '%q' "$match")")
+        done < <(_cxd_compgen "$@")
+    }
+
     # Internal invocation of compgen due to how it parses trailing '--'
     # WORD - Current (maybe partial) word
     _cxd_compgen() {
@@ -123,9 +375,14 @@ _cxd() {
     local i=0
     for item in "${COMP_WORDS[@]}"; do
         for op in ${OPERATIONS[@]}; do
-            if [ "$item" = "$op" ] && [[ "$item" =~ ^--?([a-z]{1}).*$ ]]; then
-                # Sets OP and OP_LOC if found. OP is the first letter of the operation name
-                OP=${BASH_REMATCH[1]}; 
+            if [ "$item" = "$op" ]; then
+                # Sets OP and OP_LOC if found. OP is the long name of the operation
+                case $item in
+                    -a) OP=add ;;
+                    -r) OP=remove ;;
+                    -l) OP=list ;;
+                    *) OP=${item#--} ;;
+                esac
                 OP_LOC=$i
                 break 2;
             fi
@@ -134,8 +391,13 @@ _cxd() {
     done
     if [ -n "$OP" ] && [ "$OP_LOC" != "$COMP_CWORD" ]; then
         _cxd_op_${OP}
+        return
+    fi
+    _cxd_count_free_args 1 $GLOBAL_SKIPS --record 0 --keep-going 0 --dry-run 0 --print-shell 0 --fuzzy 0 --id 0 -i 0
+    if [ $FREE_ARGS -gt 0 ] && [ $FIRST_ARG_INDEX -lt $COMP_CWORD ]; then
+        _cxd_exec
     else
-        COMPREPLY=($(_cxd_compgen -W "$OPERATIONS $GLOBAL_OPTIONS $(_cxd_names)"))
+        _cxd_reply -W "$OPERATIONS --record --keep-going --dry-run --print-shell --fuzzy --id -i $GLOBAL_OPTIONS $(_cxd_names)"
     fi
 }
 
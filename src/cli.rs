@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, os::unix::fs::PermissionsExt};
 
 use crate::error::{CxdError, Result};
 
@@ -21,9 +21,25 @@ pub fn print_op_help(op: Op) {
     use defines::*;
     let help = match op {
         Op::Add => ADD_LONG_HELP,
+        Op::AddStep => ADD_STEP_LONG_HELP,
         Op::Remove => REMOVE_LONG_HELP,
         Op::List => LIST_LONG_HELP,
         Op::Clear => CLEAR_LONG_HELP,
+        Op::Edit => EDIT_LONG_HELP,
+        Op::Export => EXPORT_LONG_HELP,
+        Op::Import => IMPORT_LONG_HELP,
+        Op::Trust => TRUST_LONG_HELP,
+        Op::Untrust => UNTRUST_LONG_HELP,
+        Op::History => HISTORY_LONG_HELP,
+        Op::Again => AGAIN_LONG_HELP,
+        Op::Deps => DEPS_LONG_HELP,
+        Op::Pick => PICK_LONG_HELP,
+        Op::Tag => TAG_LONG_HELP,
+        Op::Untag => UNTAG_LONG_HELP,
+        Op::RunTag => RUN_TAG_LONG_HELP,
+        Op::Rename => RENAME_LONG_HELP,
+        Op::Copy => COPY_LONG_HELP,
+        Op::Show => SHOW_LONG_HELP,
     };
     print_op_usage(op);
     print!("{}", help);
@@ -33,19 +49,51 @@ pub fn print_op_usage(op: Op) {
     use defines::*;
     let usage = match op {
         Op::Add => ADD_LONG_USAGE,
+        Op::AddStep => ADD_STEP_LONG_USAGE,
         Op::Remove => REMOVE_LONG_USAGE,
         Op::List => LIST_LONG_USAGE,
         Op::Clear => CLEAR_LONG_USAGE,
+        Op::Edit => EDIT_LONG_USAGE,
+        Op::Export => EXPORT_LONG_USAGE,
+        Op::Import => IMPORT_LONG_USAGE,
+        Op::Trust => TRUST_LONG_USAGE,
+        Op::Untrust => UNTRUST_LONG_USAGE,
+        Op::History => HISTORY_LONG_USAGE,
+        Op::Again => AGAIN_LONG_USAGE,
+        Op::Deps => DEPS_LONG_USAGE,
+        Op::Pick => PICK_LONG_USAGE,
+        Op::Tag => TAG_LONG_USAGE,
+        Op::Untag => UNTAG_LONG_USAGE,
+        Op::RunTag => RUN_TAG_LONG_USAGE,
+        Op::Rename => RENAME_LONG_USAGE,
+        Op::Copy => COPY_LONG_USAGE,
+        Op::Show => SHOW_LONG_USAGE,
     };
-    print!("Usage: cxd {}\n", usage);
+    println!("Usage: cxd {}", usage);
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Op {
     Add,
+    AddStep,
     Remove,
     List,
     Clear,
+    Edit,
+    Export,
+    Import,
+    Trust,
+    Untrust,
+    History,
+    Again,
+    Deps,
+    Pick,
+    Tag,
+    Untag,
+    RunTag,
+    Rename,
+    Copy,
+    Show,
 }
 
 impl Op {
@@ -53,11 +101,58 @@ impl Op {
     const fn names(&self) -> &[&'static str] {
         match self {
             Op::Add => &["--add", "-a"],
+            Op::AddStep => &["--add-step"],
             Op::Remove => &["--remove", "-r"],
             Op::List => &["--list", "-l"],
             Op::Clear => &["--clear"],
+            Op::Edit => &["--edit"],
+            Op::Export => &["--export"],
+            Op::Import => &["--import"],
+            Op::Trust => &["--trust"],
+            Op::Untrust => &["--untrust"],
+            Op::History => &["--history"],
+            Op::Again => &["--again"],
+            Op::Deps => &["--deps"],
+            Op::Pick => &["--pick"],
+            Op::Tag => &["--tag"],
+            Op::Untag => &["--untag"],
+            Op::RunTag => &["--run-tag"],
+            Op::Rename => &["--rename"],
+            Op::Copy => &["--copy"],
+            Op::Show => &["--show"],
         }
     }
+
+    const ALL: &'static [Op] = &[
+        Op::Add,
+        Op::AddStep,
+        Op::Remove,
+        Op::List,
+        Op::Clear,
+        Op::Edit,
+        Op::Export,
+        Op::Import,
+        Op::Trust,
+        Op::Untrust,
+        Op::History,
+        Op::Again,
+        Op::Deps,
+        Op::Pick,
+        Op::Tag,
+        Op::Untag,
+        Op::RunTag,
+        Op::Rename,
+        Op::Copy,
+        Op::Show,
+    ];
+
+    /// Finds the operation with a matching name, if any
+    fn from_name(name: &str) -> Option<Op> {
+        Self::ALL
+            .iter()
+            .find(|op| op.names().contains(&name))
+            .copied()
+    }
 }
 
 impl Display for Op {
@@ -78,56 +173,144 @@ pub struct CxdArgs {
     pub op: Option<Op>,
     pub op_args: Vec<String>,
     pub env: Vec<(String, String)>,
+    pub env_key: Option<String>,
+    pub deps: Vec<String>,
+    pub tags: Vec<String>,
+    pub list_tag: Option<String>,
+    pub desc: Option<String>,
     pub cwd: bool,
     pub dir: Option<String>,
+    pub local: bool,
+    pub scope: Option<String>,
+    pub shell: Option<String>,
+    pub set_cmd: Option<String>,
+    pub set_dir: Option<String>,
+    pub set_desc: Option<String>,
+    pub set_args: Option<Vec<String>>,
+    pub add_env: Vec<(String, String)>,
+    pub unset_env: Vec<String>,
+    pub add_deps: Vec<String>,
+    pub remove_deps: Vec<String>,
+    pub format: Option<String>,
+    pub skip_existing: bool,
+    pub overwrite: bool,
+    pub rename_suffix: Option<String>,
+    pub dry_run: bool,
+    pub print_shell: bool,
+    pub fuzzy: bool,
+    pub record: bool,
+    pub keep_going: bool,
+    pub failed: bool,
+    pub anywhere: bool,
+    pub since: Option<String>,
+    pub until: Option<String>,
     pub id: bool,
     pub short: bool,
+    pub grep: Option<String>,
+    pub here: bool,
     pub help: Option<HelpType>,
     pub version: bool,
 }
 
-/// This function is before handing off the parsing to `pico_args`. Add (`--add`) has a special
-/// property where it must slurp arbitrary arguments, without colliding with `cxd`'s arguments.
+/// Options which take a value, whose value must not be mistaken for a free argument
+const VALUE_OPTIONS: &[&str] = &[
+    "--env",
+    "-e",
+    "--dep",
+    "--desc",
+    "--dir",
+    "-d",
+    "--file",
+    "-f",
+    "--scope",
+    "--set-cmd",
+    "--set-dir",
+    "--set-desc",
+    "--add-env",
+    "--unset-env",
+    "--add-dep",
+    "--remove-dep",
+    "--format",
+    "--rename-suffix",
+    "--since",
+    "--until",
+    "--grep",
+];
+
+/// Shells taken as the value of a bare `--shell`, rather than as `<NAME>`
+const SHELLS: &[&str] = &["ash", "bash", "dash", "fish", "ksh", "mksh", "sh", "zsh"];
+
+/// Checks if `arg`, following a bare `--shell`, is its value. Only known shells and paths to
+/// executables are, as any other word may be `<NAME>`.
+fn names_shell(arg: &str) -> bool {
+    if arg.contains('/') {
+        std::fs::metadata(arg).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    } else {
+        SHELLS.contains(&arg)
+    }
+}
+
+/// This function is before handing off the parsing to `pico_args`. Add (`--add`, `--add-step`),
+/// edit's `--set-args` and execution have a special property where they must slurp arbitrary
+/// arguments, without colliding with `cxd`'s arguments.
 ///
 /// For example, `cxd --add ls_help ls --help`. We need to capture ["ls", "--help"] without
-/// interpreting `--help` as an option for `cxd`.
+/// interpreting `--help` as an option for `cxd`. Similarly `cxd test -- --nocapture` must pass
+/// ["--nocapture"] on to the command named `test`.
 ///
-/// This looks through the arguments, and if `--add` is specified, returns the arg position of the
-/// first argument to `--add`, `<NAME>`.
-pub fn find_add_args() -> Option<usize> {
-    // Add is greedy, and pico-args doesn't like that much
-
-    let mut raw_args = std::env::args();
+/// This looks through the arguments for the first free argument, `<NAME>`. If `--add` or
+/// `--add-step` was specified before it, returns the arg position of `<NAME>`. If no operation was specified
+/// before it, returns the arg position after `<NAME>`, where pass-through arguments begin. If
+/// `--edit` was specified, returns the arg position after `--set-args`.
+///
+/// `--tag` is an operation only when no other is given, otherwise it is an option taking a value.
+pub fn find_greedy_args() -> Option<usize> {
+    // `--tag` is resolved up front, as it may come before the operation it is an option of
+    let tag_is_op = !std::env::args()
+        .skip(1)
+        .take_while(|a| a != "--")
+        .any(|a| a != "--tag" && Op::from_name(&a).is_some());
+    // Add, edit and execution are greedy, and pico-args doesn't like that much
+    let mut op = None;
     let mut last = false;
     let mut skip_next = false;
-    raw_args
-        .position(|a| a == "-a" || a == "--add")
-        .and_then(|p| {
-            raw_args
-                .position(|a| {
-                    if last {
-                        true
-                    } else if skip_next {
-                        skip_next = false;
-                        false
-                    } else if a == "--env"
-                        || a == "-e"
-                        || a == "--dir"
-                        || a == "-d"
-                        || a == "--file"
-                        || a == "-f"
-                    {
-                        skip_next = true;
-                        false
-                    } else if a == "--" {
-                        last = true; // Need to move one forward
-                        false
-                    } else {
-                        !a.starts_with("-")
-                    }
-                })
-                .map(|a| a + p)
-        })
+    let args: Vec<_> = std::env::args().collect();
+    for (i, a) in args.iter().enumerate().skip(1) {
+        let takes_value = VALUE_OPTIONS.contains(&a.as_str())
+            || (a == "--tag" && !tag_is_op)
+            || (a == "--shell" && args.get(i + 1).is_some_and(|s| names_shell(s)));
+        if skip_next {
+            skip_next = false;
+        } else if !last && takes_value {
+            skip_next = true;
+        } else if !last && a == "--" {
+            last = true; // Need to move one forward
+        } else if !last && a == "--set-args" && op == Some(Op::Edit) {
+            return Some(i + 1);
+        } else if let Some(found) = Op::from_name(a).filter(|_| !last) {
+            op.get_or_insert(found);
+        } else if last || !a.starts_with('-') {
+            match op {
+                Some(Op::Add | Op::AddStep) => return Some(i),
+                None => return Some(i + 1),
+                // Only `--set-args` is greedy
+                Some(Op::Edit) => last = false,
+                Some(_) => return None,
+            }
+        }
+    }
+    None
+}
+
+/// Parses an env variable in the form of `<KEY>=<VALUE>`
+fn parse_env(pair: String) -> Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((k, v)) => Ok((k.to_owned(), v.to_owned())),
+        None => Err(CxdError::ArgumentParse {
+            arg: pair,
+            reason: "<KEY>=<VALUE>".into(),
+        }),
+    }
 }
 
 /// Parse CLI arguments into loosely validated struct
@@ -137,11 +320,37 @@ pub fn parse_args() -> Result<CxdArgs> {
     let mut raw_args: Vec<_> = std::env::args_os().collect();
     let mut args = CxdArgs::default();
     let mut trunc = None;
-    if let Some(i) = find_add_args() {
-        // Add is greedy, so we need to protect pico_args
-        trunc = Some(raw_args.split_off(i + 1));
+    if let Some(i) = find_greedy_args() {
+        // Add and execution are greedy, so we need to protect pico_args
+        trunc = Some(raw_args.split_off(i));
     }
     raw_args.remove(0); // Remove $0
+
+    // An optional value must be attached with '=', or name a shell, or it would be taken for
+    // <NAME>
+    let mut shell = None;
+    let mut i = 0;
+    while i < raw_args.len() {
+        let value = match raw_args[i].to_str() {
+            Some("--shell") => match raw_args.get(i + 1).and_then(|a| a.to_str()) {
+                Some(value) if names_shell(value) => raw_args.remove(i + 1).into_string().ok(),
+                _ => None,
+            },
+            Some(a) => match a.strip_prefix("--shell=") {
+                Some(value) => Some(value.to_owned()),
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        raw_args.remove(i);
+        shell = Some(value.unwrap_or_default());
+    }
     let mut pargs = pico_args::Arguments::from_vec(raw_args);
 
     // Parsing top level flags
@@ -162,6 +371,14 @@ pub fn parse_args() -> Result<CxdArgs> {
     if pargs.contains(["-a", "--add"]) {
         args.op = Some(Op::Add);
     }
+    if pargs.contains("--add-step") {
+        let old = args.op.replace(Op::AddStep);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::AddStep, old));
+        }
+    }
     if pargs.contains(["-r", "--remove"]) {
         let old = args.op.replace(Op::Remove);
         if let Some(old) = old {
@@ -186,55 +403,472 @@ pub fn parse_args() -> Result<CxdArgs> {
             return Err(CxdError::IncompatibleOperations(Op::Clear, old));
         }
     }
+    if pargs.contains("--edit") {
+        let old = args.op.replace(Op::Edit);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Edit, old));
+        }
+    }
+    if pargs.contains("--export") {
+        let old = args.op.replace(Op::Export);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Export, old));
+        }
+    }
+    if pargs.contains("--import") {
+        let old = args.op.replace(Op::Import);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Import, old));
+        }
+    }
+    if pargs.contains("--trust") {
+        let old = args.op.replace(Op::Trust);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Trust, old));
+        }
+    }
+    if pargs.contains("--untrust") {
+        let old = args.op.replace(Op::Untrust);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Untrust, old));
+        }
+    }
+    if pargs.contains("--history") {
+        let old = args.op.replace(Op::History);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::History, old));
+        }
+    }
+    if pargs.contains("--again") {
+        let old = args.op.replace(Op::Again);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Again, old));
+        }
+    }
+    if pargs.contains("--deps") {
+        let old = args.op.replace(Op::Deps);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Deps, old));
+        }
+    }
+    if pargs.contains("--pick") {
+        let old = args.op.replace(Op::Pick);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Pick, old));
+        }
+    }
+    if pargs.contains("--untag") {
+        let old = args.op.replace(Op::Untag);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Untag, old));
+        }
+    }
+    if pargs.contains("--run-tag") {
+        let old = args.op.replace(Op::RunTag);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::RunTag, old));
+        }
+    }
+    if pargs.contains("--rename") {
+        let old = args.op.replace(Op::Rename);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Rename, old));
+        }
+    }
+    if pargs.contains("--copy") {
+        let old = args.op.replace(Op::Copy);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Copy, old));
+        }
+    }
+    if pargs.contains("--show") {
+        let old = args.op.replace(Op::Show);
+        if let Some(old) = old {
+            print_short_help();
+            println!();
+            return Err(CxdError::IncompatibleOperations(Op::Show, old));
+        }
+    }
+    // Otherwise an option of add and list, see `find_greedy_args`
+    if args.op.is_none() && pargs.contains("--tag") {
+        args.op = Some(Op::Tag);
+    }
 
     // Add-specific flags
     if pargs.contains(["-c", "--cwd"]) {
-        if args.op != Some(Op::Add) {
+        if !matches!(args.op, Some(Op::Add | Op::AddStep)) {
             return Err(CxdError::OptionRequires {
                 name: "-c, --cwd".into(),
-                requires: "-a, --add".into(),
+                requires: "-a, --add or --add-step".into(),
             });
         }
+        args.cwd = true;
     }
-    args.cwd = pargs.contains(["-c", "--cwd"]);
     if let Some(path) = pargs.opt_value_from_str(["-d", "--dir"])? {
         if args.cwd {
             return Err(CxdError::OptionsIncompatible(
                 "-d, --dir".into(),
                 "-c, --cwd".into(),
             ));
-        } else if args.op != Some(Op::Add) {
+        } else if !matches!(args.op, Some(Op::Add | Op::AddStep | Op::Copy | Op::List)) {
             return Err(CxdError::OptionRequires {
                 name: "-d, --dir".into(),
-                requires: "-a, --add".into(),
+                requires: "-a, --add, --add-step, --copy or --list".into(),
             });
         }
         args.dir = Some(path);
     }
-    while let Some(pair) = pargs.opt_value_from_str::<_, String>(["-e", "--env"])? {
+    if pargs.contains(["-L", "--local"]) {
+        if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "-L, --local".into(),
+                requires: "-a, --add".into(),
+            });
+        }
+        args.local = true;
+    }
+    if let Some(path) = pargs.opt_value_from_str("--scope")? {
+        if args.local {
+            return Err(CxdError::OptionsIncompatible(
+                "--scope".into(),
+                "-L, --local".into(),
+            ));
+        } else if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "--scope".into(),
+                requires: "-a, --add".into(),
+            });
+        }
+        args.scope = Some(path);
+    }
+    if shell.is_some() {
         if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "--shell".into(),
+                requires: "-a, --add".into(),
+            });
+        }
+        args.shell = shell;
+    }
+    while let Some(pair) = pargs.opt_value_from_str::<_, String>(["-e", "--env"])? {
+        if args.op == Some(Op::List) {
+            // Only a key when filtering
+            if let Some(key) = args.env_key.replace(pair) {
+                return Err(CxdError::ArgumentParse {
+                    arg: key,
+                    reason: "-e, --env may only be given once with --list".into(),
+                });
+            }
+            continue;
+        } else if !matches!(args.op, Some(Op::Add | Op::AddStep)) {
             return Err(CxdError::OptionRequires {
                 name: "-e, --env".into(),
+                requires: "-a, --add, --add-step or --list".into(),
+            });
+        }
+        args.env.push(parse_env(pair)?);
+    }
+    while let Some(name) = pargs.opt_value_from_str("--dep")? {
+        if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "--dep".into(),
                 requires: "-a, --add".into(),
             });
         }
-        match pair.split_once('=') {
-            Some((k, v)) => args.env.push((k.to_owned(), v.to_owned())),
-            None => {
+        args.deps.push(name);
+    }
+    if let Some(desc) = pargs.opt_value_from_str("--desc")? {
+        if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "--desc".into(),
+                requires: "-a, --add".into(),
+            });
+        }
+        args.desc = Some(desc);
+    }
+    while let Some(tag) = pargs.opt_value_from_str::<_, String>("--tag")? {
+        if args.op == Some(Op::List) {
+            // Only a single tag when filtering
+            if let Some(tag) = args.list_tag.replace(tag) {
                 return Err(CxdError::ArgumentParse {
-                    arg: pair,
-                    reason: "<KEY>=<VALUE>".into(),
-                })
+                    arg: tag,
+                    reason: "--tag may only be given once with --list".into(),
+                });
             }
+            continue;
+        } else if args.op != Some(Op::Add) {
+            return Err(CxdError::OptionRequires {
+                name: "--tag".into(),
+                requires: "-a, --add or --list".into(),
+            });
+        }
+        args.tags.push(tag);
+    }
+
+    // Edit-specific arguments
+    if let Some(cmd) = pargs.opt_value_from_str("--set-cmd")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--set-cmd".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.set_cmd = Some(cmd);
+    }
+    if let Some(path) = pargs.opt_value_from_str("--set-dir")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--set-dir".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.set_dir = Some(path);
+    }
+    if let Some(desc) = pargs.opt_value_from_str("--set-desc")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--set-desc".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.set_desc = Some(desc);
+    }
+    while let Some(pair) = pargs.opt_value_from_str::<_, String>("--add-env")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--add-env".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.add_env.push(parse_env(pair)?);
+    }
+    while let Some(key) = pargs.opt_value_from_str("--unset-env")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--unset-env".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.unset_env.push(key);
+    }
+    while let Some(name) = pargs.opt_value_from_str("--add-dep")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--add-dep".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.add_deps.push(name);
+    }
+    while let Some(name) = pargs.opt_value_from_str("--remove-dep")? {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--remove-dep".into(),
+                requires: "--edit".into(),
+            });
+        }
+        args.remove_deps.push(name);
+    }
+    if pargs.contains("--set-args") {
+        if args.op != Some(Op::Edit) {
+            return Err(CxdError::OptionRequires {
+                name: "--set-args".into(),
+                requires: "--edit".into(),
+            });
+        }
+        // Adding 'set-args' arguments since we chopped them off at the beginning
+        args.set_args = Some(
+            trunc
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| a.to_string_lossy().into())
+                .collect(),
+        );
+    }
+
+    // Export and import arguments
+    if let Some(format) = pargs.opt_value_from_str("--format")? {
+        if !matches!(args.op, Some(Op::Export | Op::Import | Op::List)) {
+            return Err(CxdError::OptionRequires {
+                name: "--format".into(),
+                requires: "--export, --import or --list".into(),
+            });
+        }
+        args.format = Some(format);
+    }
+    if pargs.contains("--skip-existing") {
+        if args.op != Some(Op::Import) {
+            return Err(CxdError::OptionRequires {
+                name: "--skip-existing".into(),
+                requires: "--import".into(),
+            });
+        }
+        args.skip_existing = true;
+    }
+    if pargs.contains("--overwrite") {
+        if args.skip_existing {
+            return Err(CxdError::OptionsIncompatible(
+                "--overwrite".into(),
+                "--skip-existing".into(),
+            ));
+        } else if args.op != Some(Op::Import) {
+            return Err(CxdError::OptionRequires {
+                name: "--overwrite".into(),
+                requires: "--import".into(),
+            });
+        }
+        args.overwrite = true;
+    }
+    if let Some(suffix) = pargs.opt_value_from_str("--rename-suffix")? {
+        if args.skip_existing || args.overwrite {
+            return Err(CxdError::OptionsIncompatible(
+                "--rename-suffix".into(),
+                if args.overwrite {
+                    "--overwrite".into()
+                } else {
+                    "--skip-existing".into()
+                },
+            ));
+        } else if args.op != Some(Op::Import) {
+            return Err(CxdError::OptionRequires {
+                name: "--rename-suffix".into(),
+                requires: "--import".into(),
+            });
         }
+        args.rename_suffix = Some(suffix);
+    }
+    if pargs.contains("--dry-run") {
+        if !matches!(args.op, None | Some(Op::Import | Op::Again | Op::RunTag)) {
+            return Err(CxdError::OptionRequires {
+                name: "--dry-run".into(),
+                requires: "<NAME>, --import, --again or --run-tag".into(),
+            });
+        }
+        args.dry_run = true;
+    }
+
+    // History and again arguments
+    if pargs.contains("--failed") {
+        if !matches!(args.op, Some(Op::History | Op::Again)) {
+            return Err(CxdError::OptionRequires {
+                name: "--failed".into(),
+                requires: "--history or --again".into(),
+            });
+        }
+        args.failed = true;
+    }
+    if pargs.contains("--anywhere") {
+        if args.op != Some(Op::Again) {
+            return Err(CxdError::OptionRequires {
+                name: "--anywhere".into(),
+                requires: "--again".into(),
+            });
+        }
+        args.anywhere = true;
+    }
+    if let Some(time) = pargs.opt_value_from_str("--since")? {
+        if args.op != Some(Op::History) {
+            return Err(CxdError::OptionRequires {
+                name: "--since".into(),
+                requires: "--history".into(),
+            });
+        }
+        args.since = Some(time);
+    }
+    if let Some(time) = pargs.opt_value_from_str("--until")? {
+        if args.op != Some(Op::History) {
+            return Err(CxdError::OptionRequires {
+                name: "--until".into(),
+                requires: "--history".into(),
+            });
+        }
+        args.until = Some(time);
+    }
+
+    // Execution arguments
+    if pargs.contains("--record") {
+        if !matches!(args.op, None | Some(Op::Again | Op::RunTag)) {
+            return Err(CxdError::OptionRequires {
+                name: "--record".into(),
+                requires: "<NAME>, --again or --run-tag".into(),
+            });
+        }
+        args.record = true;
+    }
+    if pargs.contains("--keep-going") {
+        if !matches!(args.op, None | Some(Op::Again | Op::RunTag)) {
+            return Err(CxdError::OptionRequires {
+                name: "--keep-going".into(),
+                requires: "<NAME>, --again or --run-tag".into(),
+            });
+        }
+        args.keep_going = true;
+    }
+    if pargs.contains("--print-shell") {
+        if args.dry_run {
+            return Err(CxdError::OptionsIncompatible(
+                "--print-shell".into(),
+                "--dry-run".into(),
+            ));
+        } else if !matches!(args.op, None | Some(Op::Again | Op::RunTag)) {
+            return Err(CxdError::OptionRequires {
+                name: "--print-shell".into(),
+                requires: "<NAME>, --again or --run-tag".into(),
+            });
+        }
+        args.print_shell = true;
+    }
+    if pargs.contains("--fuzzy") {
+        if args.op.is_some() {
+            return Err(CxdError::OptionRequires {
+                name: "--fuzzy".into(),
+                requires: "<NAME>".into(),
+            });
+        }
+        args.fuzzy = true;
     }
 
-    // Remove-specific arguments
+    // Arguments naming commands
     if pargs.contains(["-i", "--id"]) {
-        if args.op != Some(Op::Remove) {
+        if args.fuzzy {
+            return Err(CxdError::OptionsIncompatible(
+                "-i, --id".into(),
+                "--fuzzy".into(),
+            ));
+        } else if !matches!(
+            args.op,
+            None | Some(Op::Remove | Op::Edit | Op::Export | Op::Show)
+        ) {
             return Err(CxdError::OptionRequires {
                 name: "-i, --id".into(),
-                requires: "-r, --remove".into(),
+                requires: "<NAME>, -r, --remove, --edit, --export or --show".into(),
             });
         }
         args.id = true;
@@ -242,30 +876,75 @@ pub fn parse_args() -> Result<CxdArgs> {
 
     // List-specific arguments
     if pargs.contains(["-s", "--short"]) {
-        if args.op != Some(Op::List) {
+        if args.format.is_some() {
+            return Err(CxdError::OptionsIncompatible(
+                "-s, --short".into(),
+                "--format".into(),
+            ));
+        } else if args.op != Some(Op::List) {
             return Err(CxdError::OptionRequires {
-                name: "-i, --id".into(),
-                requires: "-r, --remove".into(),
+                name: "-s, --short".into(),
+                requires: "-l, --list".into(),
             });
         }
         args.short = true;
     }
+    if let Some(pattern) = pargs.opt_value_from_str("--grep")? {
+        if args.op != Some(Op::List) {
+            return Err(CxdError::OptionRequires {
+                name: "--grep".into(),
+                requires: "-l, --list".into(),
+            });
+        }
+        args.grep = Some(pattern);
+    }
+    if pargs.contains("--here") {
+        if args.dir.is_some() {
+            return Err(CxdError::OptionsIncompatible(
+                "--here".into(),
+                "-d, --dir".into(),
+            ));
+        } else if args.op != Some(Op::List) {
+            return Err(CxdError::OptionRequires {
+                name: "--here".into(),
+                requires: "-l, --list".into(),
+            });
+        }
+        args.here = true;
+    }
 
     for arg in pargs.finish() {
         args.op_args.push(arg.to_string_lossy().into());
     }
 
-    if let Some(Op::Add) = &mut args.op {
-        if args.op_args.len() > 0 {
-            return Err(CxdError::ArgumentParse {
-                arg: args.op_args.join(" "),
-                reason: "unexpected argument".into(),
-            })?;
+    match args.op {
+        Some(Op::Add | Op::AddStep) => {
+            if !args.op_args.is_empty() {
+                return Err(CxdError::ArgumentParse {
+                    arg: args.op_args.join(" "),
+                    reason: "unexpected argument".into(),
+                });
+            }
+            // Adding 'add' arguments since we chopped them off at the beginning
+            for arg in trunc.unwrap_or_default() {
+                args.op_args.push(arg.to_string_lossy().into());
+            }
         }
-        // Adding 'add' arguments since we chopped them off at the beginning
-        for arg in trunc.unwrap_or_default() {
-            args.op_args.push(arg.to_string_lossy().into());
+        None => {
+            // A '--' may separate <NAME> from its pass-through arguments
+            if args.op_args.first().is_some_and(|a| a == "--") {
+                args.op_args.remove(0);
+            }
+            let mut trunc = trunc.unwrap_or_default();
+            if trunc.first().is_some_and(|a| a == "--") {
+                trunc.remove(0);
+            }
+            // Adding pass-through arguments since we chopped them off at the beginning
+            for arg in trunc {
+                args.op_args.push(arg.to_string_lossy().into());
+            }
         }
+        _ => {}
     }
     Ok(args)
 }
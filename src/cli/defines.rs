@@ -4,7 +4,8 @@ use const_format::{concatcp, str_replace};
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub const USAGE: &str = "cxd [OPTIONS] <NAME>|<OPERATION>";
+pub const USAGE: &str = "cxd [OPTIONS] <NAME> [--] [ARG]...
+       cxd [OPTIONS] <OPERATION>";
 
 const HELP_ARG_DESC: &str = "Show this help message";
 const HELP_OP_ARG_DESC: &str = "Show a help message for this operation";
@@ -17,19 +18,123 @@ const FILE_LONG_HELP: &str = concatcp!(FILE_DESC, r#"
 Defaults to first of: $CXD_CACHE_DIR/cxd.cache, $XDG_CACHE_HOME/cxd.cache, $HOME/.cache/cxd.cache
 "#);
 
+const RECORD_DESC: &str = "Wait for the command to finish, recording how the run went";
+const RECORD_LONG_USAGE: &str = "--record";
+const RECORD_LONG_HELP: &str = concatcp!(RECORD_DESC, r#"
+
+Must be given before <NAME>. Instead of replacing itself with the command, cxd runs it as a
+child process, forwarding SIGTERM, SIGHUP, SIGUSR1 and SIGUSR2 to it, then exits with the same
+status. The run is recorded in the history along with its duration and exit code, see
+--history.
+
+Enabled for all executions if $CXD_RECORD is set to a value other than 0.
+"#);
+
+const KEEP_GOING_DESC: &str = "Run the remaining steps of a command after a step fails";
+const KEEP_GOING_LONG_USAGE: &str = "--keep-going";
+const KEEP_GOING_LONG_HELP: &str = concatcp!(KEEP_GOING_DESC, r#"
+
+Must be given before <NAME>. cxd still exits with the status of the first step which failed.
+A step killed by a signal, such as by ^C, always stops the command.
+"#);
+
+const DRY_RUN_DESC: &str = "Show what running the command would do, without running it";
+const DRY_RUN_LONG_USAGE: &str = "--dry-run";
+const DRY_RUN_LONG_HELP: &str = concatcp!(DRY_RUN_DESC, r#"
+
+Must be given before <NAME>. Shows each program which would run, including dependencies and
+steps, with its working directory, the env variables set by the command, the program resolved
+against $PATH and the full argument list, all with placeholders expanded. Nothing is recorded,
+and the cache or project file need not be trusted, so commands can be reviewed first.
+"#);
+
+const PRINT_SHELL_DESC: &str = "Print the command as a quoted shell line, without running it";
+const PRINT_SHELL_LONG_USAGE: &str = "--print-shell";
+const PRINT_SHELL_LONG_HELP: &str = concatcp!(PRINT_SHELL_DESC, r#"
+
+Must be given before <NAME>. The same as --dry-run, but printed as a line which can be pasted
+into a POSIX shell, with each program on its own line joined by &&.
+"#);
+
+const FUZZY_DESC: &str = "Run the most similar command when none is named <NAME>";
+const FUZZY_LONG_USAGE: &str = "--fuzzy";
+const FUZZY_LONG_HELP: &str = concatcp!(FUZZY_DESC, r#"
+
+Must be given before <NAME>. A command matches if the characters of <NAME> appear in its
+name in order, such as bld for build, or if its name is a typo or two away from <NAME>,
+ignoring case. A single match runs, printing its command line to stderr first, otherwise cxd
+asks which to run. Without --fuzzy, similar names are only suggested.
+"#);
+
+const ID_DESC: &str = "Run the cached command whose ID is <NAME>";
+const ID_LONG_USAGE: &str = "-i, --id";
+const ID_LONG_HELP: &str = concatcp!(ID_DESC, r#"
+
+Must be given before <NAME>. IDs are shown first by --list, and find a command whatever its
+scope, without being shadowed by project files. Also accepted by --remove, --edit, --export
+and --show.
+"#);
+
 const ADD_DESC: &str = "Add a new command to the database";
 pub const ADD_LONG_USAGE: &str = "-a, --add [OPTIONS] <NAME> <CMD> [ARG]...";
 pub const ADD_LONG_HELP: &str = concatcp!(ADD_DESC, r#"
 
 Arguments:
-  <NAME>             Name of command to add, must be unique to database
+  <NAME>             Name of command to add, must be unique within its scope
   <CMD>              Executable to run, may be bare name within $PATH, or absolute path.
   [ARG]              One or more arguments to CMD
 
+Placeholders:
+  ARG, DIR and VAL may contain placeholders, expanded at execution time.
+  {1}, {2}, ...      The Nth argument passed after <NAME> at execution
+  {@}                All arguments passed after <NAME> at execution
+  {cwd}              Directory cxd was invoked from
+  {name}             Name of the command
+  {git_root}         Root of the git repository containing the CWD
+  {VAR:-default}     Environment variable VAR, or default if unset. ':-default' is optional
+  Other braces, such as '{print $1}' or '{{.Names}}', are left as is. Unknown words in
+  braces are rejected, write '\{print}' to pass one as is.
+
 Add Options:
   -c, --cwd          Save CWD as command's working directory
   -d, --dir DIR      Save DIR as command's working directory
   -e, --env ENV=VAL  Save an env variable to the command's environment
+  --dep NAME         Run the command named NAME first, see --deps
+  --tag TAG          Add the command to the group TAG, see --run-tag
+  --desc TEXT        Describe the command, such as why it exists or what its args mean. May
+                     span several lines
+  -L, --local        Scope NAME to CWD, only resolving from CWD or below
+  --scope DIR        Scope NAME to DIR, only resolving from DIR or below
+  --shell[=SHELL]    Save CMD as a script run with SHELL -c, or $SHELL if not given. SHELL may
+                     follow a space instead of '=' when it is sh, bash, zsh, dash, ksh, mksh,
+                     ash, fish or a path to an executable, otherwise it is taken for <NAME>
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+
+Shell Scripts:
+  With --shell, CMD is a script which may use pipes, redirects and globs, so must be quoted
+  as a single argument. The script itself is never expanded, ARGs and extra arguments at
+  execution are passed to it as $1, $2, ..., with NAME as $0.
+"#);
+
+const ADD_STEP_DESC: &str = "Add a step to run after an existing command";
+pub const ADD_STEP_LONG_USAGE: &str = "--add-step [OPTIONS] <NAME> <CMD> [ARG]...";
+pub const ADD_STEP_LONG_HELP: &str = concatcp!(ADD_STEP_DESC, r#"
+
+Steps run in the order added, after the command's own CMD, each only once the previous succeeds,
+see --keep-going. Steps share the command's env variables and directory, unless given their
+own. Unused extra arguments at execution are appended to the last step.
+
+Arguments:
+  <NAME>             Name of command to add the step to, resolved from CWD the same as when
+                     executing
+  <CMD>              Executable to run, may be bare name within $PATH, or absolute path.
+  [ARG]              One or more arguments to CMD, which may contain placeholders
+
+Add Step Options:
+  -c, --cwd          Save CWD as the step's working directory
+  -d, --dir DIR      Save DIR as the step's working directory
+  -e, --env ENV=VAL  Save an env variable to the step's environment
   -h, --help         "#, HELP_OP_ARG_DESC, r#"
   --version          "#, VERSION_ARG_DESC, r#"
 "#);
@@ -39,7 +144,8 @@ pub const REMOVE_LONG_USAGE: &str = "-r, --remove [OPTIONS] <COMMAND>";
 pub const REMOVE_LONG_HELP: &str = concatcp!(REMOVE_DESC, r#"
 
 Arguments:
-  <COMMAND>          Selector for command, by default this is the command name
+  <COMMAND>          Selector for command, by default this is the command name, resolved
+                     from CWD the same as when executing
 
 Remove Options:
   -i, --id ID        Interpret SELECTOR as the command's internal ID
@@ -51,8 +157,28 @@ pub const LIST_DESC: &str = "List available commands";
 pub const LIST_LONG_USAGE: &str = "-l, --list [OPTIONS]";
 pub const LIST_LONG_HELP: &str = concatcp!(LIST_DESC, r#"
 
+Formats:
+  json               An array of objects with the keys id, name, origin, scope, dir, shell,
+                     command, args, envs, deps, steps, tags and description. Unset values are
+                     null or empty
+  tsv                One line per command, with the tab separated fields id, name, scope, dir,
+                     shell, command, args, envs, tags and description. Args, envs and tags are
+                     shell quoted words, and backslash, tab, newline and carriage return are
+                     escaped as \\, \t, \n and \r
+  null               Names only, each followed by a NUL byte
+
+Fields are only ever added to these formats, after the existing ones.
+
+Filters narrow any output, and must all match when combined. --grep ignores ASCII case.
+
 List Options:
   -s, --short        Short output -- name only
+  --format FMT       Machine readable output, one of json, tsv or null
+  --grep PATTERN     Only commands whose name, program, args or description contain PATTERN
+  --here             Only commands run from the CWD or below
+  -d, --dir DIR      Only commands run from DIR or below
+  -e, --env KEY      Only commands setting the env variable KEY
+  --tag TAG          Only commands tagged TAG
   -h, --help         "#, HELP_OP_ARG_DESC, r#"
   --version          "#, VERSION_ARG_DESC, r#"
 "#);
@@ -66,15 +192,322 @@ Clear Options:
   --version          "#, VERSION_ARG_DESC, r#"
 "#);
 
+pub const EDIT_DESC: &str = "Edit an existing command in place";
+pub const EDIT_LONG_USAGE: &str = "--edit [OPTIONS] <NAME> [--set-args [ARG]...]";
+pub const EDIT_LONG_HELP: &str = concatcp!(EDIT_DESC, r#"
+
+Without any options, opens the command in $VISUAL or $EDITOR, saving it once the editor exits.
+
+Arguments:
+  <NAME>             Name of command to edit, resolved from CWD the same as when executing
+
+Edit Options:
+  --set-cmd CMD      Replace the executable to run
+  --set-dir DIR      Replace the command's working directory, an empty DIR removes it
+  --set-desc TEXT    Replace the command's description, an empty TEXT removes it
+  --add-env ENV=VAL  Add or replace an env variable of the command's environment
+  --unset-env ENV    Remove an env variable from the command's environment
+  --add-dep NAME     Add a dependency on the command named NAME, run after existing ones
+  --remove-dep NAME  Remove a dependency on the command named NAME
+  --set-args [ARG]   Replace all arguments with the remaining arguments, must be last
+  -i, --id           Interpret <NAME> as the command's ID, as shown by --list
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const EXPORT_DESC: &str = "Export commands to stdout as a versioned document";
+pub const EXPORT_LONG_USAGE: &str = "--export [OPTIONS] [NAME]...";
+pub const EXPORT_LONG_HELP: &str = concatcp!(EXPORT_DESC, r#"
+
+Arguments:
+  [NAME]             Names of commands to export, resolved from CWD the same as when
+                     executing. Exports all commands if none are given
+
+Export Options:
+  --format FMT       Document format, one of json (default) or toml
+  -i, --id           Interpret each [NAME] as a command's ID, as shown by --list
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const IMPORT_DESC: &str = "Import commands from a document written by --export";
+pub const IMPORT_LONG_USAGE: &str = "--import [OPTIONS] <FILE>";
+pub const IMPORT_LONG_HELP: &str = concatcp!(IMPORT_DESC, r#"
+
+Commands are imported all at once, so any error leaves the database unchanged. By default,
+importing a command with the same name and scope as an existing command is an error.
+
+Arguments:
+  <FILE>             Document to import, or '-' to read from stdin
+
+Import Options:
+  --format FMT       Document format, one of json or toml. Defaults to toml for files ending
+                     in '.toml', json otherwise
+  --skip-existing    Keep existing commands, skipping conflicting imports
+  --overwrite        Replace existing commands with conflicting imports
+  --rename-suffix S  Import conflicting commands as NAME + S, numbered if still conflicting
+  --dry-run          Show what would be imported, without changing the database
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const TRUST_DESC: &str = "Allow running commands from a cache or project file";
+pub const TRUST_LONG_USAGE: &str = "--trust [FILE]";
+pub const TRUST_LONG_HELP: &str = concatcp!(TRUST_DESC, r#"
+
+Commands only run from cache and project files trusted with their current contents. Unless
+trusted, running a command shows all commands of its file and asks before running any of them,
+or fails if not interactive. Changes made by cxd itself keep a trusted cache trusted.
+
+Trusted files are recorded in $CXD_TRUST_FILE, defaulting to cxd.trust in the same directory as
+the default cache file.
+
+Arguments:
+  [FILE]             Project file to trust, trusts the cache file if not given
+
+Trust Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const UNTRUST_DESC: &str = "Revoke trust of a cache or project file";
+pub const UNTRUST_LONG_USAGE: &str = "--untrust [FILE]";
+pub const UNTRUST_LONG_HELP: &str = concatcp!(UNTRUST_DESC, r#"
+
+Arguments:
+  [FILE]             Project file to stop trusting, the cache file if not given
+
+Untrust Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const HISTORY_DESC: &str = "Show recorded runs of commands";
+pub const HISTORY_LONG_USAGE: &str = "--history [OPTIONS] [NAME]";
+pub const HISTORY_LONG_HELP: &str = concatcp!(HISTORY_DESC, r#"
+
+Runs supervised with --record or $CXD_RECORD, along with multi-step, dependent and tagged
+commands, are recorded with their start time, CWD, extra arguments, duration and exit code,
+oldest shown first. Other runs replace cxd with the command, so are never recorded. Runs are
+kept when their command is removed.
+
+Arguments:
+  [NAME]             Only show runs of commands named NAME
+
+History Options:
+  --failed           Only show runs which exited with a non-zero code
+  --since TIME       Only show runs started at or after TIME
+  --until TIME       Only show runs started at or before TIME
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+
+TIME is a unix timestamp, a local date and time such as '2024-09-17 13:00', or a duration
+before now such as 30m, 2h, 3d or 1w.
+"#);
+
+pub const AGAIN_DESC: &str = "Run the last command again";
+pub const AGAIN_LONG_USAGE: &str = "--again [OPTIONS]";
+pub const AGAIN_LONG_HELP: &str = concatcp!(AGAIN_DESC, r#"
+
+Runs the most recent recorded run from CWD again, with the same extra arguments, supervised so
+the new run is recorded too. Only supervised runs are recorded, see --history. The command line
+being run is printed to stderr first.
+
+Again Options:
+  --failed           Run the most recent run which exited with a non-zero code
+  --anywhere         Consider runs from any directory, running again from its directory
+  --record           "#, RECORD_DESC, r#"
+  --keep-going       "#, KEEP_GOING_DESC, r#"
+  --dry-run          "#, DRY_RUN_DESC, r#"
+  --print-shell      "#, PRINT_SHELL_DESC, r#"
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const DEPS_DESC: &str = "Show the order a command and its dependencies run in";
+pub const DEPS_LONG_USAGE: &str = "--deps <NAME>";
+pub const DEPS_LONG_HELP: &str = concatcp!(DEPS_DESC, r#"
+
+Before running a command, cxd runs the commands it depends on, and their dependencies, in the
+order they were added. Each runs at most once per execution, however many commands depend on
+it, without extra arguments. The first to fail stops the execution, regardless of --keep-going.
+Dependencies are resolved by name from CWD, the same as the command itself.
+
+Shows dependencies as a tree, each numbered by the order it runs in. A dependency shown earlier
+in the tree is marked (*), and its dependencies are not repeated.
+
+Arguments:
+  <NAME>             Name of command to show, resolved from CWD the same as when executing
+
+Deps Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const PICK_DESC: &str = "Pick a command to run, edit or delete from a full screen list";
+pub const PICK_LONG_USAGE: &str = "--pick";
+pub const PICK_LONG_HELP: &str = concatcp!(PICK_DESC, r#"
+
+Lists the commands which resolve from CWD, those scoped nearest to it first, then global
+commands run from within it, then all other global commands. Typing filters the list by name,
+the same as --fuzzy, with a preview of the selected command below. Runs over the controlling
+terminal, even if stdin or stdout are redirected.
+
+Keys:
+  enter              Run the selected command, printing its name to stderr first
+  up, down, ^p, ^n   Select the previous or next command
+  ^e                 Edit the selected command in $VISUAL or $EDITOR
+  ^d                 Delete the selected command, after confirming
+  ^u                 Clear the filter
+  tab                Switch between typing a filter and the keys below
+  e, d               The same as ^e and ^d
+  j, k               Select the next or previous command
+  /                  Switch back to typing a filter
+  esc, ^c, q         Quit without running anything, q only when not typing
+
+Pick Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const TAG_DESC: &str = "Add a command to one or more groups";
+pub const TAG_LONG_USAGE: &str = "--tag <NAME> <TAG>...";
+pub const TAG_LONG_HELP: &str = concatcp!(TAG_DESC, r#"
+
+Tags group commands beyond their names, such as ci, release or debug, to list them with
+--list --tag or run them all with --run-tag. A command may have any number of tags. Tags must
+not be empty or contain whitespace.
+
+Only an operation on its own, otherwise --tag is an option of --add or --list.
+
+Arguments:
+  <NAME>             Name of command to tag, resolved from CWD the same as when executing
+  <TAG>              Tags to add, existing tags are kept
+
+Tag Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const UNTAG_DESC: &str = "Remove a command from one or more groups";
+pub const UNTAG_LONG_USAGE: &str = "--untag <NAME> <TAG>...";
+pub const UNTAG_LONG_HELP: &str = concatcp!(UNTAG_DESC, r#"
+
+Arguments:
+  <NAME>             Name of command to untag, resolved from CWD the same as when executing
+  <TAG>              Tags to remove, tags the command does not have are ignored
+
+Untag Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const RUN_TAG_DESC: &str = "Run every command with a tag, one after another";
+pub const RUN_TAG_LONG_USAGE: &str = "--run-tag [OPTIONS] <TAG>";
+pub const RUN_TAG_LONG_HELP: &str = concatcp!(RUN_TAG_DESC, r#"
+
+Runs the tagged commands which resolve from CWD in the order they were added, project commands
+first, each printing its name to stderr first. A tagged name resolving to an untagged command,
+such as one scoped nearer to CWD, is skipped. Always supervised, each command is recorded in
+the history. The first command to fail stops the rest, and cxd exits with its status.
+
+Dependencies run before the first command needing them, and each command runs at most once,
+even if another tagged command depends on it. No extra arguments are passed.
+
+Arguments:
+  <TAG>              Tag of the commands to run
+
+Run Tag Options:
+  --record           "#, RECORD_DESC, r#"
+  --keep-going       "#, KEEP_GOING_DESC, r#"
+  --dry-run          "#, DRY_RUN_DESC, r#"
+  --print-shell      "#, PRINT_SHELL_DESC, r#"
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const RENAME_DESC: &str = "Rename a command, keeping everything else";
+pub const RENAME_LONG_USAGE: &str = "--rename <OLD> <NEW>";
+pub const RENAME_LONG_HELP: &str = concatcp!(RENAME_DESC, r#"
+
+The command keeps its scope, args, envs, steps, deps and tags, and its recorded runs are
+renamed along with it. Commands scoped within its scope and depending on <OLD> are changed to
+depend on <NEW>.
+
+Arguments:
+  <OLD>              Name of command to rename, resolved from CWD the same as when executing
+  <NEW>              New name of the command, must be unique within its scope
+
+Rename Options:
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const COPY_DESC: &str = "Copy a command under a new name";
+pub const COPY_LONG_USAGE: &str = "--copy [OPTIONS] <SRC> <DST>";
+pub const COPY_LONG_HELP: &str = concatcp!(COPY_DESC, r#"
+
+The copy has the same scope, args, envs, steps, deps and tags as the original, but none of its
+recorded runs. Use --dir to create a variant running somewhere else, such as a sibling project,
+which is then scoped to that directory and may keep the same name.
+
+Arguments:
+  <SRC>              Name of command to copy, resolved from CWD the same as when executing
+  <DST>              Name of the copy, must be unique within its scope
+
+Copy Options:
+  -d, --dir DIR      Save DIR as the copy's working directory and scope, instead of the
+                     original's
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
+pub const SHOW_DESC: &str = "Show a single command in detail";
+pub const SHOW_LONG_USAGE: &str = "--show [OPTIONS] <NAME>";
+pub const SHOW_LONG_HELP: &str = concatcp!(SHOW_DESC, r#"
+
+Shows everything saved for the command, in the same form as --list.
+
+Arguments:
+  <NAME>             Name of command to show, resolved from CWD the same as when executing
+
+Show Options:
+  -i, --id           Interpret <NAME> as the command's ID, as shown by --list
+  -h, --help         "#, HELP_OP_ARG_DESC, r#"
+  --version          "#, VERSION_ARG_DESC, r#"
+"#);
+
 pub const LONG_HELP: &str = concatcp!(
 r#"Usage: "#, USAGE, r#"
 Arguments:
-  <NAME>   Name of command to execute
+  <NAME>   Name of command to execute. Commands scoped to CWD or its nearest parent
+           are preferred over global commands of the same name. Commands of the
+           nearest .cxd.toml project file are preferred over all cached commands.
+  [ARG]    Extra arguments appended to the command's saved arguments. Everything
+           after <NAME> is passed through, an optional leading '--' is discarded.
 
 Options:
   "#, FILE_LONG_USAGE, r#"
       "#, str_replace!(FILE_LONG_HELP, "\n", "\n      "), r#"
 
+  "#, RECORD_LONG_USAGE, r#"
+      "#, str_replace!(RECORD_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, KEEP_GOING_LONG_USAGE, r#"
+      "#, str_replace!(KEEP_GOING_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, DRY_RUN_LONG_USAGE, r#"
+      "#, str_replace!(DRY_RUN_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, PRINT_SHELL_LONG_USAGE, r#"
+      "#, str_replace!(PRINT_SHELL_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, FUZZY_LONG_USAGE, r#"
+      "#, str_replace!(FUZZY_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, ID_LONG_USAGE, r#"
+      "#, str_replace!(ID_LONG_HELP, "\n", "\n      "), r#"
+
   -h
       Show the short version of this help message
 
@@ -88,6 +521,9 @@ Operations:
   "#, ADD_LONG_USAGE, r#"
       "#, str_replace!(ADD_LONG_HELP, "\n", "\n      "), r#"
 
+  "#, ADD_STEP_LONG_USAGE, r#"
+      "#, str_replace!(ADD_STEP_LONG_HELP, "\n", "\n      "), r#"
+
   "#, REMOVE_LONG_USAGE, r#"
       "#, str_replace!(REMOVE_LONG_HELP, "\n", "\n      "), r#"
 
@@ -96,6 +532,51 @@ Operations:
 
   "#, CLEAR_LONG_USAGE, r#"
       "#, str_replace!(CLEAR_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, EDIT_LONG_USAGE, r#"
+      "#, str_replace!(EDIT_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, EXPORT_LONG_USAGE, r#"
+      "#, str_replace!(EXPORT_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, IMPORT_LONG_USAGE, r#"
+      "#, str_replace!(IMPORT_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, TRUST_LONG_USAGE, r#"
+      "#, str_replace!(TRUST_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, UNTRUST_LONG_USAGE, r#"
+      "#, str_replace!(UNTRUST_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, HISTORY_LONG_USAGE, r#"
+      "#, str_replace!(HISTORY_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, AGAIN_LONG_USAGE, r#"
+      "#, str_replace!(AGAIN_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, DEPS_LONG_USAGE, r#"
+      "#, str_replace!(DEPS_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, PICK_LONG_USAGE, r#"
+      "#, str_replace!(PICK_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, TAG_LONG_USAGE, r#"
+      "#, str_replace!(TAG_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, UNTAG_LONG_USAGE, r#"
+      "#, str_replace!(UNTAG_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, RUN_TAG_LONG_USAGE, r#"
+      "#, str_replace!(RUN_TAG_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, RENAME_LONG_USAGE, r#"
+      "#, str_replace!(RENAME_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, COPY_LONG_USAGE, r#"
+      "#, str_replace!(COPY_LONG_HELP, "\n", "\n      "), r#"
+
+  "#, SHOW_LONG_USAGE, r#"
+      "#, str_replace!(SHOW_LONG_HELP, "\n", "\n      "), r#"
 "#);
 
 pub const SHORT_HELP: &str = concatcp!(
@@ -103,16 +584,39 @@ r#"Usage: "#, USAGE, r#"
 
 Arguments:
   <NAME>   Name of command to execute
+  [ARG]    Extra arguments appended to the command
 
 Options:
   -f, --file <FILE>                "#, FILE_DESC, r#"
+  --record                         "#, RECORD_DESC, r#"
+  --keep-going                     "#, KEEP_GOING_DESC, r#"
+  --dry-run                        "#, DRY_RUN_DESC, r#"
+  --print-shell                    "#, PRINT_SHELL_DESC, r#"
+  --fuzzy                          "#, FUZZY_DESC, r#"
+  -i, --id                         "#, ID_DESC, r#"
   -h                               "#, HELP_ARG_DESC, r#"
   --help                           Show the long version of this help message
   --version                        "#, VERSION_ARG_DESC, r#"
 
 Operations:
   -a, --add <NAME> <CMD> [ARG]...  "#, ADD_DESC, r#"
+  --add-step <NAME> <CMD> [ARG]... "#, ADD_STEP_DESC, r#"
   -r, --remove <COMMAND>           "#, REMOVE_DESC, r#"
   -l, --list                       "#, LIST_DESC, r#"
   --clear                          "#, CLEAR_DESC, r#"
+  --edit <NAME>                    "#, EDIT_DESC, r#"
+  --export [NAME]...               "#, EXPORT_DESC, r#"
+  --import <FILE>                  "#, IMPORT_DESC, r#"
+  --trust [FILE]                   "#, TRUST_DESC, r#"
+  --untrust [FILE]                 "#, UNTRUST_DESC, r#"
+  --history [NAME]                 "#, HISTORY_DESC, r#"
+  --again                          "#, AGAIN_DESC, r#"
+  --deps <NAME>                    "#, DEPS_DESC, r#"
+  --pick                           "#, PICK_DESC, r#"
+  --tag <NAME> <TAG>...            "#, TAG_DESC, r#"
+  --untag <NAME> <TAG>...          "#, UNTAG_DESC, r#"
+  --run-tag <TAG>                  "#, RUN_TAG_DESC, r#"
+  --rename <OLD> <NEW>             "#, RENAME_DESC, r#"
+  --copy <SRC> <DST>               "#, COPY_DESC, r#"
+  --show <NAME>                    "#, SHOW_DESC, r#"
 "#);
@@ -1,65 +1,392 @@
-use std::{os::unix::process::CommandExt, path::PathBuf};
+use std::{
+    collections::BTreeSet,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    command_store::{ArgRow, CmdRow, EnvRow},
+    command_store::{ArgRow, CmdRow, DepRow, EnvRow, StepArgRow, StepEnvRow, StepRow, TagRow},
     error::{CxdError, Result},
+    shell::{self, quote},
+    template::{Context, Placeholder, Template},
 };
 
-#[derive(Debug)]
+/// Where a command is defined
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Origin {
+    /// The cache file, which may be written to
+    #[default]
+    Cache,
+    /// A read-only project file, at the given path
+    Project(PathBuf),
+}
+
+/// A program run after the command's own, in order
+#[derive(Clone, Debug, Default)]
+pub struct Step {
+    pub command: String,
+    // An empty path runs the step in the command's directory
+    pub dir: PathBuf,
+    pub args: Vec<String>,
+    // Set after the command's envs, so may override them
+    pub envs: Vec<(String, String)>,
+}
+
+impl Step {
+    pub fn new(step_row: StepRow, arg_rows: Vec<StepArgRow>, env_rows: Vec<StepEnvRow>) -> Self {
+        Self {
+            command: step_row.cmd,
+            dir: step_row.dir.into(),
+            args: arg_rows.into_iter().map(|a| a.data).collect(),
+            envs: env_rows.into_iter().map(|e| (e.key, e.value)).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct Command {
     pub id: i64,
     pub name: String,
+    // A script rather than a program if `shell` is set
     pub command: String,
+    // Shell running `command` as a script, an empty string signifies `$SHELL`
+    pub shell: Option<String>,
     // Due to Sqlite not considering NULL as unique, an empty string here signifies None
     pub dir: PathBuf,
+    // Directory the name is scoped to, an empty path signifies a global command
+    pub scope: PathBuf,
     pub args: Vec<String>,
     pub envs: Vec<(String, String)>,
+    // Run after `command` succeeds, stopping at the first failure
+    pub steps: Vec<Step>,
+    // Names of commands to run first, resolved the same as this command
+    pub deps: Vec<String>,
+    // Groups the command belongs to, sorted and unique
+    pub tags: Vec<String>,
+    // Free text notes, which may span several lines, empty if none
+    pub description: String,
+    // Whether placeholders are expanded, false for commands created before they existed
+    pub templated: bool,
+    pub origin: Origin,
 }
 
 impl Command {
-    pub fn new(cmd_row: CmdRow, arg_rows: Vec<ArgRow>, env_rows: Vec<EnvRow>) -> Self {
+    pub fn new(
+        cmd_row: CmdRow,
+        arg_rows: Vec<ArgRow>,
+        env_rows: Vec<EnvRow>,
+        steps: Vec<Step>,
+        dep_rows: Vec<DepRow>,
+        tag_rows: Vec<TagRow>,
+    ) -> Self {
         Self {
             id: cmd_row.id,
             name: cmd_row.name,
             command: cmd_row.cmd,
+            shell: cmd_row.shell,
             dir: cmd_row.dir.into(),
+            scope: cmd_row.scope.into(),
             args: arg_rows.into_iter().map(|a| a.data).collect(),
             envs: env_rows.into_iter().map(|a| (a.key, a.value)).collect(),
+            steps,
+            deps: dep_rows.into_iter().map(|d| d.name).collect(),
+            tags: tag_rows.into_iter().map(|t| t.name).collect(),
+            description: cmd_row.description,
+            templated: cmd_row.templated,
+            origin: Origin::Cache,
         }
     }
 
-    pub fn exec(self) -> Result<()> {
+    /// Parses the arguments, directory and env values of the command and its steps as templates
+    ///
+    /// # Returns
+    /// The templates, or none if the command isn't templated.
+    fn templates(&self) -> Result<Vec<(&str, Template)>> {
+        if !self.templated {
+            return Ok(vec![]);
+        }
+        let dir = self.dir.to_str().unwrap_or_default();
+        let steps = self.steps.iter().flat_map(|step| {
+            std::iter::once(step.dir.to_str().unwrap_or_default())
+                .chain(step.args.iter().map(String::as_str))
+                .chain(step.envs.iter().map(|(_, v)| v.as_str()))
+        });
+        std::iter::once(dir)
+            .chain(self.args.iter().map(String::as_str))
+            .chain(self.envs.iter().map(|(_, v)| v.as_str()))
+            .chain(steps)
+            .map(|t| Ok((t, Template::parse(t)?)))
+            .collect()
+    }
+
+    /// Checks whether the command is global or scoped to `dir` or one of its parents, so may
+    /// resolve from `dir`, unless shadowed by a nearer scope
+    pub fn in_scope(&self, dir: &Path) -> bool {
+        self.scope.as_os_str().is_empty() || dir.starts_with(&self.scope)
+    }
+
+    /// Validates the placeholders and dependencies of the command, without expanding or
+    /// resolving them
+    pub fn validate(&self) -> Result<()> {
+        let mut positions = BTreeSet::new();
+        let mut last_template = "";
+        for (template, parsed) in self.templates()? {
+            for placeholder in parsed.placeholders() {
+                if let Placeholder::Position(n) = placeholder {
+                    if positions.last().is_none_or(|last| n > last) {
+                        last_template = template;
+                    }
+                    positions.insert(*n);
+                }
+            }
+        }
+        if let Some(&max) = positions.last() {
+            if let Some(missing) = (1..max).find(|p| !positions.contains(p)) {
+                return Err(CxdError::InvalidTemplate {
+                    template: last_template.into(),
+                    reason: format!("{{{max}}} is used without {{{missing}}}"),
+                });
+            }
+        }
+        for (i, dep) in self.deps.iter().enumerate() {
+            if *dep == self.name {
+                return Err(CxdError::DependencyCycle(vec![dep.clone(), dep.clone()]));
+            }
+            if self.deps[..i].contains(dep) {
+                return Err(CxdError::ArgumentParse {
+                    arg: dep.clone(),
+                    reason: "duplicate dependency".into(),
+                });
+            }
+        }
+        for tag in &self.tags {
+            if tag.is_empty() || tag.contains(char::is_whitespace) {
+                return Err(CxdError::ArgumentParse {
+                    arg: tag.clone(),
+                    reason: "tags must not be empty or contain whitespace".into(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Expands all placeholders of the command and its steps, consuming runtime arguments
+    ///
+    /// Runtime arguments after the highest referenced position are appended to the arguments of
+    /// the last step, unless `{@}` is used. Commands which aren't templated are left as is, with
+    /// every runtime argument appended.
+    ///
+    /// # Args
+    /// * `extra_args` - Runtime arguments passed after the command name
+    pub fn render(mut self, extra_args: Vec<String>) -> Result<Self> {
+        if !self.templated {
+            match self.steps.last_mut() {
+                Some(step) => step.args.extend(extra_args),
+                None => self.args.extend(extra_args),
+            }
+            return Ok(self);
+        }
+        let mut consumed = 0;
+        let mut uses_all = false;
+        for (_, template) in self.templates()? {
+            for placeholder in template.placeholders() {
+                match placeholder {
+                    Placeholder::Position(n) => consumed = consumed.max(*n),
+                    Placeholder::All => uses_all = true,
+                    _ => {}
+                }
+            }
+        }
+
+        let cwd = std::env::current_dir()?;
+        let ctx = Context {
+            name: &self.name,
+            args: &extra_args,
+            cwd: &cwd,
+        };
+        let expand_dir = |dir: &PathBuf| -> Result<PathBuf> {
+            Ok(Template::parse(dir.to_str().unwrap_or_default())?
+                .expand(&ctx)?
+                .into())
+        };
+        let expand_args = |args: &[String]| -> Result<Vec<String>> {
+            let mut ret = vec![];
+            for arg in args {
+                ret.extend(Template::parse(arg)?.expand_args(&ctx)?);
+            }
+            Ok(ret)
+        };
+        let expand_envs = |envs: &[(String, String)]| -> Result<Vec<(String, String)>> {
+            envs.iter()
+                .map(|(k, v)| Ok((k.clone(), Template::parse(v)?.expand(&ctx)?)))
+                .collect()
+        };
+
+        let dir = expand_dir(&self.dir)?;
+        let mut args = expand_args(&self.args)?;
+        let envs = expand_envs(&self.envs)?;
+        let mut steps = self
+            .steps
+            .iter()
+            .map(|step| {
+                Ok(Step {
+                    command: step.command.clone(),
+                    dir: expand_dir(&step.dir)?,
+                    args: expand_args(&step.args)?,
+                    envs: expand_envs(&step.envs)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !uses_all {
+            let last_args = match steps.last_mut() {
+                Some(step) => &mut step.args,
+                None => &mut args,
+            };
+            last_args.extend(extra_args.iter().skip(consumed).cloned());
+        }
+        Ok(Self {
+            dir,
+            args,
+            envs,
+            steps,
+            ..self
+        })
+    }
+
+    /// Builds a process for the command, then each of its steps, which must already be rendered
+    pub fn processes(&self) -> Vec<std::process::Command> {
+        let mut process = match &self.shell {
+            Some(shell) => {
+                let shell = match shell.as_str() {
+                    "" => std::env::var("SHELL")
+                        .ok()
+                        .filter(|s| !s.is_empty())
+                        .unwrap_or("sh".into()),
+                    shell => shell.into(),
+                };
+                // Arguments after the script are its positional parameters, starting from $0
+                let mut process = std::process::Command::new(shell);
+                process.arg("-c").arg(&self.command).arg(&self.name);
+                process
+            }
+            None => std::process::Command::new(&self.command),
+        };
+        process.args(&self.args).envs(self.envs.iter().cloned());
         if self.dir.components().next().is_some() {
-            std::env::set_current_dir(self.dir)
-                .map_err(|e| CxdError::Exec(self.name.clone(), e))?;
+            process.current_dir(&self.dir);
+        }
+        let mut ret = vec![process];
+        for step in &self.steps {
+            let mut process = std::process::Command::new(&step.command);
+            process
+                .args(&step.args)
+                .envs(self.envs.iter().chain(&step.envs).cloned());
+            let dir = if step.dir.components().next().is_some() {
+                &step.dir
+            } else {
+                &self.dir
+            };
+            if dir.components().next().is_some() {
+                process.current_dir(dir);
+            }
+            ret.push(process);
         }
+        ret
+    }
+
+    /// Replaces the current process with the command, after expanding placeholders
+    ///
+    /// Steps are never run, as nothing remains to run them, so commands with steps must be
+    /// supervised instead.
+    ///
+    /// # Args
+    /// * `extra_args` - Runtime arguments passed after the command name
+    pub fn exec(self, extra_args: Vec<String>) -> Result<()> {
+        let cmd = self.render(extra_args)?;
+        let mut process = cmd.processes().swap_remove(0);
         // execvp requires program name to be first arg too
-        Err(CxdError::Exec(
-            self.name,
-            std::process::Command::new(self.command.clone())
-                .args(self.args)
-                .envs(self.envs)
-                .exec(),
-        ))
+        Err(CxdError::Exec(cmd.name, process.exec()))
     }
 }
 
 impl std::fmt::Display for Command {
     /// Command formatting - the plus (`+`) flag can be used to display the ID.
+    ///
+    /// Paths, arguments and env values are quoted the same as a POSIX shell, see [`shell::quote`].
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "[{}]: {}", self.id, self.name)?;
+        let path = |p: &PathBuf| quote(p.to_str().unwrap_or("invalid path")).into_owned();
+        match &self.origin {
+            Origin::Cache => writeln!(f, "[{}]: {}", self.id, self.name)?,
+            // Project commands have no ID
+            Origin::Project(origin) => {
+                writeln!(f, "[-]: {}", self.name)?;
+                writeln!(f, "  origin: {}", path(origin))?;
+            }
+        }
+        // Prose rather than a value, so left unquoted with its lines aligned. Empty lines keep
+        // their indent, as an empty line separates commands in `--list`
+        for (i, line) in self.description.lines().enumerate() {
+            let key = if i == 0 { "desc:" } else { "     " };
+            writeln!(f, "  {key} {line}")?;
+        }
+        if self.scope.components().next().is_some() {
+            writeln!(f, "  scope: {}", path(&self.scope))?;
+        } else {
+            writeln!(f, "  scope: global")?;
+        }
         if self.envs.len() == 1 {
-            writeln!(f, "  env: {}={}", self.envs[0].0, self.envs[0].1)?;
+            writeln!(f, "  env: {}={}", self.envs[0].0, quote(&self.envs[0].1))?;
         } else if self.envs.len() > 1 {
             writeln!(f, "  env:")?;
             for (k, v) in self.envs.iter() {
-                writeln!(f, "    {k}={v}")?;
+                writeln!(f, "    {k}={}", quote(v))?;
             }
         }
         if self.dir.components().next().is_some() {
-            writeln!(f, "  dir: {}", self.dir.to_str().unwrap_or("invalid path"))?;
+            writeln!(f, "  dir: {}", path(&self.dir))?;
+        }
+        if !self.templated {
+            writeln!(f, "  templated: no")?;
+        }
+        if !self.deps.is_empty() {
+            writeln!(
+                f,
+                "  deps: {}",
+                shell::join(self.deps.iter().map(String::as_str))
+            )?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(
+                f,
+                "  tags: {}",
+                shell::join(self.tags.iter().map(String::as_str))
+            )?;
+        }
+        let args = shell::join(self.args.iter().map(String::as_str));
+        match &self.shell {
+            Some(shell) => {
+                writeln!(
+                    f,
+                    "  shell: {}",
+                    if shell.is_empty() { "$SHELL" } else { shell }
+                )?;
+                if !self.args.is_empty() {
+                    writeln!(f, "  args: {args}")?;
+                }
+                write!(f, "  script: {}", quote(&self.command))?;
+            }
+            None => write!(f, "  cmd: {} {args}", quote(&self.command))?,
+        }
+        for step in &self.steps {
+            let args = shell::join(step.args.iter().map(String::as_str));
+            write!(f, "\n  step: {} {args}", quote(&step.command))?;
+            if step.dir.components().next().is_some() {
+                write!(f, "\n    dir: {}", path(&step.dir))?;
+            }
+            for (k, v) in &step.envs {
+                write!(f, "\n    env: {k}={}", quote(v))?;
+            }
         }
-        write!(f, "  cmd: {} {}", self.command, self.args.join(" "))?;
         Ok(())
     }
 }
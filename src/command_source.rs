@@ -0,0 +1,179 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    command::Command,
+    error::{CxdError, Result},
+    fuzzy,
+    listing::ListFilter,
+};
+
+/// A source of commands which can be resolved and listed
+pub trait CommandSource {
+    /// Attempts to resolve a command by name, as seen from a directory
+    ///
+    /// # Args
+    /// * `name` - Name of command to search for
+    /// * `dir` - Absolute directory to resolve scopes from, usually the CWD
+    ///
+    /// # Returns
+    /// The found command, or `None` if none found.
+    fn resolve(&self, name: &str, dir: &Path) -> Result<Option<Command>>;
+
+    /// Fetches all commands of the source
+    fn fetch_all(&self) -> Result<Vec<Command>>;
+
+    /// Fetches the commands of the source matching `filter`
+    fn fetch_matching(&self, filter: &ListFilter) -> Result<Vec<Command>> {
+        let mut cmds = self.fetch_all()?;
+        cmds.retain(|c| filter.matches(c));
+        Ok(cmds)
+    }
+
+    /// Fetches the commands tagged `tag`, each as its name resolves from `dir`
+    ///
+    /// Names resolving to an untagged command, such as one scoped closer to `dir`, are omitted.
+    /// Commands are in the order of [`Self::fetch_matching`], each appearing once.
+    fn fetch_tagged(&self, tag: &str, dir: &Path) -> Result<Vec<Command>> {
+        let filter = ListFilter {
+            tag: Some(tag.into()),
+            ..Default::default()
+        };
+        let mut ret = vec![];
+        let mut seen = HashSet::new();
+        for cmd in self.fetch_matching(&filter)? {
+            if !seen.insert(cmd.name.clone()) {
+                continue;
+            }
+            if let Some(found) = self.resolve(&cmd.name, dir)? {
+                if found.tags.iter().any(|t| t == tag) {
+                    ret.push(found);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Suggests names of commands in scope of `dir` which are similar to `name`, best first
+    fn suggest(&self, name: &str, dir: &Path) -> Result<Vec<String>> {
+        let cmds = self.fetch_all()?;
+        let names = cmds
+            .iter()
+            .filter(|c| c.in_scope(dir))
+            .map(|c| c.name.as_str());
+        Ok(fuzzy::rank(name, names)
+            .into_iter()
+            .map(String::from)
+            .collect())
+    }
+
+    /// Resolves a command along with everything it depends on, in the order they must run
+    ///
+    /// Dependencies are resolved by name from `dir`, the same as the command itself, and ordered
+    /// depth first in the order declared. Each appears once, however many commands depend on it.
+    ///
+    /// # Returns
+    /// The commands to run, ending with the named command, or `None` if it was not found.
+    fn resolve_deps(&self, name: &str, dir: &Path) -> Result<Option<Vec<Command>>> {
+        let Some(cmd) = self.resolve(name, dir)? else {
+            return Ok(None);
+        };
+        self.order_deps(cmd, dir).map(Some)
+    }
+
+    /// Orders an already found command after everything it depends on, the same as
+    /// [`Self::resolve_deps`]
+    fn order_deps(&self, cmd: Command, dir: &Path) -> Result<Vec<Command>> {
+        let mut order = vec![];
+        visit(self, cmd, dir, &mut vec![], &mut order)?;
+        Ok(order)
+    }
+}
+
+/// Orders the dependencies of `cmd`, then `cmd` itself, unless already ordered
+///
+/// # Args
+/// * `path` - Names of the commands depending on `cmd`, to detect cycles
+/// * `order` - Commands ordered so far
+fn visit<S: CommandSource + ?Sized>(
+    source: &S,
+    cmd: Command,
+    dir: &Path,
+    path: &mut Vec<String>,
+    order: &mut Vec<Command>,
+) -> Result<()> {
+    if order.iter().any(|c| c.name == cmd.name) {
+        return Ok(());
+    }
+    if let Some(start) = path.iter().position(|name| *name == cmd.name) {
+        let mut cycle = path.split_off(start);
+        cycle.push(cmd.name);
+        return Err(CxdError::DependencyCycle(cycle));
+    }
+    path.push(cmd.name.clone());
+    for dep in &cmd.deps {
+        let found = source
+            .resolve(dep, dir)?
+            .ok_or_else(|| CxdError::DependencyNotFound {
+                name: cmd.name.clone(),
+                dep: dep.clone(),
+            })?;
+        visit(source, found, dir, path, order)?;
+    }
+    path.pop();
+    order.push(cmd);
+    Ok(())
+}
+
+/// Layers several sources, where earlier sources shadow later ones by name
+#[derive(Default)]
+pub struct Layered<'a> {
+    sources: Vec<&'a dyn CommandSource>,
+}
+
+impl<'a> Layered<'a> {
+    /// Adds a source, shadowed by all previously added sources
+    pub fn push(&mut self, source: &'a dyn CommandSource) {
+        self.sources.push(source);
+    }
+}
+
+impl CommandSource for Layered<'_> {
+    /// Resolves from the first source with a command named `name` in scope of `dir`
+    fn resolve(&self, name: &str, dir: &Path) -> Result<Option<Command>> {
+        for source in &self.sources {
+            if let Some(cmd) = source.resolve(name, dir)? {
+                return Ok(Some(cmd));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetches the commands of every source, omitting those shadowed by name
+    fn fetch_all(&self) -> Result<Vec<Command>> {
+        let mut ret = vec![];
+        let mut seen = HashSet::new();
+        for source in &self.sources {
+            let cmds = source.fetch_all()?;
+            let names: Vec<_> = cmds.iter().map(|c| c.name.clone()).collect();
+            ret.extend(cmds.into_iter().filter(|c| !seen.contains(&c.name)));
+            seen.extend(names);
+        }
+        Ok(ret)
+    }
+
+    /// Fetches the matching commands of every source, omitting those shadowed by name, whether
+    /// or not the shadowing command matches
+    fn fetch_matching(&self, filter: &ListFilter) -> Result<Vec<Command>> {
+        let mut ret = vec![];
+        let mut seen = HashSet::new();
+        for (i, source) in self.sources.iter().enumerate() {
+            let cmds = source.fetch_matching(filter)?;
+            ret.extend(cmds.into_iter().filter(|c| !seen.contains(&c.name)));
+            // Nothing is shadowed by the last source
+            if i + 1 < self.sources.len() {
+                seen.extend(source.fetch_all()?.into_iter().map(|c| c.name));
+            }
+        }
+        Ok(ret)
+    }
+}
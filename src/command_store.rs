@@ -1,18 +1,125 @@
-use std::path::Path;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    command::Command,
+    command::{Command, Origin, Step},
+    command_source::CommandSource,
     error::{CxdError, Result},
+    history::{HistoryFilter, Run},
+    listing::ListFilter,
 };
-use rusqlite::{ffi::Error, Connection, ErrorCode};
+use rusqlite::{ffi::Error, Connection, ErrorCode, Transaction, TransactionBehavior};
 
 mod arg_row;
 mod cmd_row;
+mod dep_row;
 mod env_row;
+mod migration;
+mod step_arg_row;
+mod step_env_row;
+mod step_row;
+mod tag_row;
 
 pub use arg_row::ArgRow;
 pub use cmd_row::CmdRow;
+pub use dep_row::DepRow;
 pub use env_row::EnvRow;
+pub use step_arg_row::StepArgRow;
+pub use step_env_row::StepEnvRow;
+pub use step_row::StepRow;
+pub use tag_row::TagRow;
+
+/// Matches commands named `?1` which are global, or scoped to `?2` or one of its parents
+const RESOLVE_CLAUSE: &str = "name = ?1 AND (
+    scope = ''
+    OR scope = ?2
+    OR scope = '/'
+    OR substr(?2, 1, length(scope) + 1) = scope || '/'
+)";
+
+/// Matches runs by the fields of [`HistoryFilter`], bound by [`CommandStore::history_params`]
+const HISTORY_CLAUSE: &str = "(?1 IS NULL OR name = ?1)
+    AND (NOT ?2 OR exit_code != 0)
+    AND (?3 IS NULL OR started_at >= ?3)
+    AND (?4 IS NULL OR started_at <= ?4)
+    AND (?5 IS NULL OR cwd = ?5)";
+
+/// Matches commands by the fields of [`ListFilter`], bound by [`CommandStore::list_params`]
+const LIST_CLAUSE: &str = "(?1 IS NULL
+        OR instr(lower(name), lower(?1)) > 0
+        OR instr(lower(cmd), lower(?1)) > 0
+        OR instr(lower(description), lower(?1)) > 0
+        OR EXISTS (SELECT 1 FROM cxd_arg WHERE cmd_id = cxd_cmd.id AND instr(lower(data), lower(?1)) > 0))
+    AND (?2 IS NULL OR dir = ?2 OR substr(dir, 1, length(rtrim(?2, '/')) + 1) = rtrim(?2, '/') || '/')
+    AND (?3 IS NULL OR EXISTS (SELECT 1 FROM cxd_env WHERE cmd_id = cxd_cmd.id AND key = ?3))
+    AND (?4 IS NULL OR EXISTS (SELECT 1 FROM cxd_cmd_tag JOIN cxd_tag ON cxd_tag.id = tag_id
+        WHERE cmd_id = cxd_cmd.id AND name = ?4))";
+
+/// How long to wait on other `cxd` processes holding the database lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Switches the cache to WAL mode, unless it already is
+///
+/// Changing the journal mode needs the cache to itself, which the busy timeout doesn't wait for,
+/// so this retries while other processes have it open, for up to [`BUSY_TIMEOUT`].
+fn enable_wal(c: &Connection) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        let mode: String = c.query_row("PRAGMA journal_mode", (), |row| row.get(0))?;
+        if mode.eq_ignore_ascii_case("wal") {
+            return Ok(());
+        }
+        match c.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        {
+            Ok(mode) if mode.eq_ignore_ascii_case("wal") => return Ok(()),
+            Err(e) if e.sqlite_error_code() != Some(ErrorCode::DatabaseBusy) => Err(e)?,
+            _ if start.elapsed() >= BUSY_TIMEOUT => {
+                return Err(CxdError::Sql(rusqlite::Error::SqliteFailure(
+                    Error::new(rusqlite::ffi::SQLITE_BUSY),
+                    None,
+                )))
+            }
+            _ => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+/// Checks for a violation of a UNIQUE constraint
+fn is_unique_violation(e: &rusqlite::Error) -> bool {
+    matches!(
+        e,
+        rusqlite::Error::SqliteFailure(
+            Error {
+                code: ErrorCode::ConstraintViolation,
+                extended_code: 2067,
+            },
+            _,
+        )
+    )
+}
+
+/// How [`CommandStore::import`] resolves a command conflicting with an existing one
+pub enum Conflict {
+    /// Fails the import
+    Fail,
+    /// Keeps the existing command
+    Skip,
+    /// Replaces the existing command
+    Overwrite,
+    /// Appends a suffix to the imported name, then a counter while it still conflicts
+    Rename(String),
+}
+
+/// The outcome of importing a single command
+pub enum Imported {
+    Created(Command),
+    Skipped(Command),
+    Overwritten(Command),
+    /// Imported under a new name, along with the original name
+    Renamed(Command, String),
+}
 
 /// Represents a connection to the database for operating on commands
 pub struct CommandStore {
@@ -20,7 +127,7 @@ pub struct CommandStore {
 }
 
 impl CommandStore {
-    /// Initializes the table schemas and required configuration variables
+    /// Migrates the table schemas and sets required configuration variables
     ///
     /// # Args
     /// * `path` - Path to backing database file, created if not present
@@ -28,12 +135,14 @@ impl CommandStore {
     /// # Returns
     /// A handle to a database connection for operations on commands.
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let c = Connection::open(path)?;
+        let mut c = Connection::open(&path)?;
+        c.busy_timeout(BUSY_TIMEOUT)?;
+        // Migrating first refuses a cache from a newer cxd before anything is written to it
+        migration::migrate(&mut c, path.as_ref())?;
+        // Allows readers to continue while another process writes
+        enable_wal(&c)?;
         // Enable foreign key support
         c.execute("PRAGMA foreign_keys = ON", ())?;
-        CmdRow::init(&c)?;
-        ArgRow::init(&c)?;
-        EnvRow::init(&c)?;
         Ok(Self { c })
     }
 
@@ -45,37 +154,238 @@ impl CommandStore {
     /// # Returns
     /// The `id` of the newly created command, or `None` if one already exists with matching unique constraints.
     pub fn insert(&self, cmd: &Command) -> Result<Option<i64>> {
+        let tx = self.write_transaction()?;
+        let id = self.insert_within(cmd)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Inserts a command, expecting the caller to hold a write transaction
+    fn insert_within(&self, cmd: &Command) -> Result<Option<i64>> {
         // Creating command entry
-        let mut command_stmt = self
-            .c
-            .prepare("INSERT INTO cxd_cmd (name, cmd, dir) VALUES (?1, ?2, ?3) RETURNING (id)")?;
-        let mut result = command_stmt.query((
-            &cmd.name,
-            &cmd.command,
-            cmd.dir.to_str().unwrap_or_default(),
-        ))?;
-        let id: i64 = match result.next() {
-            Ok(Some(row)) => row.get("id")?,
-            Err(rusqlite::Error::SqliteFailure(
-                Error {
-                    code: ErrorCode::ConstraintViolation,
-                    extended_code: 2067,
-                },
-                _,
-            )) => {
-                // Already exists
-                return Ok(None);
+        let inserted = self.c.query_row(
+            "INSERT INTO cxd_cmd (name, cmd, dir, scope, shell, description, templated) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) RETURNING (id)",
+            (
+                &cmd.name,
+                &cmd.command,
+                cmd.dir.to_str().unwrap_or_default(),
+                cmd.scope.to_str().unwrap_or_default(),
+                &cmd.shell,
+                &cmd.description,
+                cmd.templated,
+            ),
+            |row| row.get("id"),
+        );
+        let id: i64 = match inserted {
+            Ok(id) => id,
+            // Already exists
+            Err(e) if is_unique_violation(&e) => return Ok(None),
+            Err(e) => Err(e)?,
+        };
+
+        self.insert_children(id, cmd)?;
+        Ok(Some(id))
+    }
+
+    /// Attempts to replace an existing command, matched by `cmd.id`
+    ///
+    /// The command row, args and envs are all replaced within a single transaction.
+    ///
+    /// # Args
+    /// * `cmd` - Command to replace the existing command with
+    ///
+    /// # Returns
+    /// `false` if no command with a matching `id` exists.
+    pub fn update(&self, cmd: &Command) -> Result<bool> {
+        let tx = self.write_transaction()?;
+        let updated = self.update_within(cmd)?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Replaces a command, expecting the caller to hold a write transaction
+    fn update_within(&self, cmd: &Command) -> Result<bool> {
+        let updated = self.c.execute(
+            "UPDATE cxd_cmd SET name = ?1, cmd = ?2, dir = ?3, scope = ?4, shell = ?5, \
+             description = ?6, templated = ?7 WHERE id = ?8",
+            (
+                &cmd.name,
+                &cmd.command,
+                cmd.dir.to_str().unwrap_or_default(),
+                cmd.scope.to_str().unwrap_or_default(),
+                &cmd.shell,
+                &cmd.description,
+                cmd.templated,
+                cmd.id,
+            ),
+        );
+        match updated {
+            Ok(0) => return Ok(false),
+            Ok(_) => {}
+            Err(e) if is_unique_violation(&e) => {
+                return Err(CxdError::CommandExists(cmd.name.clone()))
             }
             Err(e) => Err(e)?,
-            Ok(None) => Err(CxdError::CommandExists(cmd.name.clone()))?,
+        }
+        self.c
+            .execute("DELETE FROM cxd_arg WHERE cmd_id = ?1", [cmd.id])?;
+        self.c
+            .execute("DELETE FROM cxd_env WHERE cmd_id = ?1", [cmd.id])?;
+        // Cascades to the args and envs of the steps
+        self.c
+            .execute("DELETE FROM cxd_step WHERE cmd_id = ?1", [cmd.id])?;
+        self.c
+            .execute("DELETE FROM cxd_dep WHERE cmd_id = ?1", [cmd.id])?;
+        self.c
+            .execute("DELETE FROM cxd_cmd_tag WHERE cmd_id = ?1", [cmd.id])?;
+        self.insert_children(cmd.id, cmd)?;
+        self.delete_unused_tags()?;
+        Ok(true)
+    }
+
+    /// Renames a command, keeping its args, envs, tags and recorded runs
+    ///
+    /// Runs recorded under the old name are renamed along with the command, so `--history` and
+    /// `--again` follow it. So are the deps of commands scoped within its scope, which resolve
+    /// deps by name and would otherwise no longer find it.
+    ///
+    /// # Args
+    /// * `id` - ID of command to rename
+    /// * `name` - New name, which must be unique within the command's scope
+    ///
+    /// # Returns
+    /// The renamed command, or `None` if no command with a matching `id` exists.
+    pub fn rename(&self, id: i64, name: &str) -> Result<Option<Command>> {
+        let tx = self.write_transaction()?;
+        let Some(old) = self.get_by_id(id)? else {
+            return Ok(None);
         };
+        let renamed = self
+            .c
+            .execute("UPDATE cxd_cmd SET name = ?1 WHERE id = ?2", (name, id));
+        match renamed {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(e) if is_unique_violation(&e) => return Err(CxdError::CommandExists(name.into())),
+            Err(e) => Err(e)?,
+        }
+        self.c.execute(
+            "UPDATE cxd_history SET name = ?1 WHERE cmd_id = ?2",
+            (name, id),
+        )?;
+        self.c.execute(
+            "UPDATE cxd_dep SET name = ?1 WHERE name = ?2 AND cmd_id IN (
+                SELECT id FROM cxd_cmd
+                WHERE ?3 = '' OR ?3 = '/' OR scope = ?3 OR substr(scope, 1, length(?3) + 1) = ?3 || '/'
+            )",
+            (name, &old.name, old.scope.to_str().unwrap_or_default()),
+        )?;
+        let cmd = self.get_by_id(id)?;
+        tx.commit()?;
+        Ok(cmd)
+    }
 
+    /// Copies a command under a new name, duplicating its args, envs, steps, deps and tags
+    ///
+    /// The copy keeps the scope of the original, unless given a `dir` which it is then scoped to.
+    /// Recorded runs stay with the original.
+    ///
+    /// # Args
+    /// * `id` - ID of command to copy
+    /// * `name` - Name of the copy, which must be unique within its scope
+    /// * `dir` - Absolute working directory and scope of the copy, or `None` to keep those of the
+    ///   original
+    ///
+    /// # Returns
+    /// The copy, or `None` if no command with a matching `id` exists.
+    pub fn copy(&self, id: i64, name: &str, dir: Option<&Path>) -> Result<Option<Command>> {
+        let tx = self.write_transaction()?;
+        let Some(mut cmd) = self.get_by_id(id)? else {
+            return Ok(None);
+        };
+        cmd.name = name.into();
+        if let Some(dir) = dir {
+            cmd.dir = dir.into();
+            cmd.scope = dir.into();
+        }
+        cmd.id = self
+            .insert_within(&cmd)?
+            .ok_or_else(|| CxdError::CommandExists(cmd.name.clone()))?;
+        tx.commit()?;
+        Ok(Some(cmd))
+    }
+
+    /// Imports commands within a single transaction, resolving name conflicts by `conflict`
+    ///
+    /// A conflict is an existing command with the same name and scope. Any error, including a
+    /// conflict under [`Conflict::Fail`], leaves the database untouched.
+    ///
+    /// # Args
+    /// * `cmds` - Commands to import, `cmd.id` will be ignored
+    /// * `conflict` - How to resolve conflicts with existing commands
+    /// * `dry_run` - Rolls back once finished, only reporting what would change
+    ///
+    /// # Returns
+    /// What was done with each command, in the same order as `cmds`.
+    pub fn import(
+        &self,
+        cmds: Vec<Command>,
+        conflict: &Conflict,
+        dry_run: bool,
+    ) -> Result<Vec<Imported>> {
+        let tx = self.write_transaction()?;
+        let mut ret = vec![];
+        for mut cmd in cmds {
+            if let Some(id) = self.insert_within(&cmd)? {
+                cmd.id = id;
+                ret.push(Imported::Created(cmd));
+                continue;
+            }
+            match conflict {
+                Conflict::Fail => return Err(CxdError::CommandExists(cmd.name)),
+                Conflict::Skip => ret.push(Imported::Skipped(cmd)),
+                Conflict::Overwrite => {
+                    cmd.id = self
+                        .find(&cmd.name, &cmd.scope)?
+                        .ok_or_else(|| CxdError::CommandNotFound {
+                            name: cmd.name.clone(),
+                            suggestions: vec![],
+                        })?
+                        .id;
+                    self.update_within(&cmd)?;
+                    ret.push(Imported::Overwritten(cmd));
+                }
+                Conflict::Rename(suffix) => {
+                    let original = std::mem::take(&mut cmd.name);
+                    for n in 1.. {
+                        cmd.name = match n {
+                            1 => format!("{original}{suffix}"),
+                            n => format!("{original}{suffix}{n}"),
+                        };
+                        if let Some(id) = self.insert_within(&cmd)? {
+                            cmd.id = id;
+                            break;
+                        }
+                    }
+                    ret.push(Imported::Renamed(cmd, original));
+                }
+            }
+        }
+        if !dry_run {
+            tx.commit()?;
+        }
+        Ok(ret)
+    }
+
+    /// Inserts the rows with a FK to the command `id`
+    fn insert_children(&self, id: i64, cmd: &Command) -> Result<()> {
         // Creating args
         let mut args_stmt = self
             .c
-            .prepare("INSERT INTO cxd_arg (data, cmd_id) VALUES (?1, ?2)")?;
-        for arg in &cmd.args {
-            args_stmt.execute((arg, id))?;
+            .prepare("INSERT INTO cxd_arg (data, position, cmd_id) VALUES (?1, ?2, ?3)")?;
+        for (position, arg) in cmd.args.iter().enumerate() {
+            args_stmt.execute((arg, position, id))?;
         }
 
         // Creating envs
@@ -85,37 +395,108 @@ impl CommandStore {
         for env in &cmd.envs {
             envs_stmt.execute((&env.0, &env.1, id))?;
         }
-        Ok(Some(id))
+
+        // Creating deps
+        let mut deps_stmt = self
+            .c
+            .prepare("INSERT INTO cxd_dep (name, position, cmd_id) VALUES (?1, ?2, ?3)")?;
+        for (position, dep) in cmd.deps.iter().enumerate() {
+            deps_stmt.execute((dep, position, id))?;
+        }
+
+        // Creating tags, along with their links to the command
+        let mut tags_stmt = self
+            .c
+            .prepare("INSERT OR IGNORE INTO cxd_tag (name) VALUES (?1)")?;
+        let mut tag_ids_stmt = self.c.prepare("SELECT id FROM cxd_tag WHERE name = ?1")?;
+        let mut cmd_tags_stmt = self
+            .c
+            .prepare("INSERT OR IGNORE INTO cxd_cmd_tag (cmd_id, tag_id) VALUES (?1, ?2)")?;
+        for tag in &cmd.tags {
+            tags_stmt.execute([tag])?;
+            let tag_id: i64 = tag_ids_stmt.query_row([tag], |row| row.get("id"))?;
+            cmd_tags_stmt.execute((id, tag_id))?;
+        }
+
+        // Creating steps, along with their args and envs
+        let mut steps_stmt = self.c.prepare(
+            "INSERT INTO cxd_step (cmd, dir, position, cmd_id) VALUES (?1, ?2, ?3, ?4) RETURNING (id)",
+        )?;
+        let mut step_args_stmt = self
+            .c
+            .prepare("INSERT INTO cxd_step_arg (data, position, step_id) VALUES (?1, ?2, ?3)")?;
+        let mut step_envs_stmt = self
+            .c
+            .prepare("INSERT INTO cxd_step_env (key, value, step_id) VALUES (?1, ?2, ?3)")?;
+        for (position, step) in cmd.steps.iter().enumerate() {
+            let step_id: i64 = steps_stmt.query_row(
+                (
+                    &step.command,
+                    step.dir.to_str().unwrap_or_default(),
+                    position,
+                    id,
+                ),
+                |row| row.get("id"),
+            )?;
+            for (position, arg) in step.args.iter().enumerate() {
+                step_args_stmt.execute((arg, position, step_id))?;
+            }
+            for env in &step.envs {
+                step_envs_stmt.execute((&env.0, &env.1, step_id))?;
+            }
+        }
+        Ok(())
     }
 
-    /// Attempts to get a command by name
+    /// Attempts to resolve a command by name, as seen from a directory
+    ///
+    /// Commands scoped to `dir` or its nearest parent take precedence, falling back to a global
+    /// command of the same name.
     ///
     /// # Args
     /// * `name` - Name of command to search for
+    /// * `dir` - Absolute directory to resolve scopes from, usually the CWD
     ///
     /// # Returns
     /// The found command, or `None` if none found.
-    pub fn get_by_name(&self, name: &str) -> Result<Option<Command>> {
-        let mut command_stmt = self.c.prepare("SELECT * FROM cxd_cmd WHERE name = ?1")?;
-        let mut rows = command_stmt.query([name])?;
+    pub fn resolve(&self, name: &str, dir: &Path) -> Result<Option<Command>> {
+        let mut command_stmt = self.c.prepare(&format!(
+            "SELECT * FROM cxd_cmd WHERE {RESOLVE_CLAUSE} ORDER BY length(scope) DESC LIMIT 1"
+        ))?;
+        let mut rows = command_stmt.query((name, dir.to_str().unwrap_or_default()))?;
         Ok(self.assemble(&mut rows)?.pop())
     }
 
-    /// Attempts to delete a command by name
+    /// Attempts to find a command by its exact name and scope, without resolving parent scopes
     ///
     /// # Args
-    /// * `name` - Name of command to search for and delete
+    /// * `name` - Name of command to search for
+    /// * `scope` - Scope of the command, an empty path for global commands
     ///
     /// # Returns
-    /// The deleted command, or `None` if none found.
-    pub fn delete_by_name(&self, name: &str) -> Result<Option<Command>> {
-        let mut delete_cmd_stmt = self
+    /// The found command, or `None` if none found.
+    pub fn find(&self, name: &str, scope: &Path) -> Result<Option<Command>> {
+        let mut command_stmt = self
             .c
-            .prepare("DELETE FROM cxd_cmd WHERE name = ?1 RETURNING *")?;
-        let mut rows = delete_cmd_stmt.query([name])?;
+            .prepare("SELECT * FROM cxd_cmd WHERE name = ?1 AND scope = ?2")?;
+        let mut rows = command_stmt.query((name, scope.to_str().unwrap_or_default()))?;
         Ok(self.assemble(&mut rows)?.pop())
     }
 
+    /// Attempts to delete a command by name, resolving scopes the same as [`Self::resolve`]
+    ///
+    /// # Args
+    /// * `name` - Name of command to search for and delete
+    /// * `dir` - Absolute directory to resolve scopes from, usually the CWD
+    ///
+    /// # Returns
+    /// The deleted command, or `None` if none found.
+    pub fn delete_by_name(&self, name: &str, dir: &Path) -> Result<Option<Command>> {
+        let tx = self.write_transaction()?;
+        let cmd = self.resolve(name, dir)?;
+        self.delete(tx, cmd)
+    }
+
     /// Attempts to delete a command by ID
     ///
     /// # Args
@@ -124,13 +505,44 @@ impl CommandStore {
     /// # Returns
     /// The deleted command, or `None` if none found.
     pub fn delete_by_id(&self, id: i64) -> Result<Option<Command>> {
-        let mut delete_cmd_stmt = self
-            .c
-            .prepare("DELETE FROM cxd_cmd WHERE id = ?1 RETURNING *")?;
-        let mut rows = delete_cmd_stmt.query([id])?;
+        let tx = self.write_transaction()?;
+        let cmd = self.get_by_id(id)?;
+        self.delete(tx, cmd)
+    }
+
+    /// Attempts to fetch a command by ID, regardless of its scope
+    ///
+    /// # Args
+    /// * `id` - ID of command to search for, as shown by `--list`
+    ///
+    /// # Returns
+    /// The found command, or `None` if none found.
+    pub fn get_by_id(&self, id: i64) -> Result<Option<Command>> {
+        let mut command_stmt = self.c.prepare("SELECT * FROM cxd_cmd WHERE id = ?1")?;
+        let mut rows = command_stmt.query([id])?;
         Ok(self.assemble(&mut rows)?.pop())
     }
 
+    /// Deletes a fetched command along with all rows with a FK to it, committing `tx`
+    fn delete(&self, tx: Transaction, cmd: Option<Command>) -> Result<Option<Command>> {
+        if let Some(cmd) = &cmd {
+            tx.execute("DELETE FROM cxd_cmd WHERE id = ?1", [cmd.id])?;
+            self.delete_unused_tags()?;
+        }
+        tx.commit()?;
+        Ok(cmd)
+    }
+
+    /// Deletes tags no longer carried by any command, expecting the caller to hold a write
+    /// transaction
+    fn delete_unused_tags(&self) -> Result<()> {
+        self.c.execute(
+            "DELETE FROM cxd_tag WHERE id NOT IN (SELECT tag_id FROM cxd_cmd_tag)",
+            (),
+        )?;
+        Ok(())
+    }
+
     /// Fetches all commands in the database
     pub fn fetch_all(&self) -> Result<Vec<Command>> {
         let mut command_stmt = self.c.prepare("SELECT * FROM cxd_cmd")?;
@@ -138,11 +550,158 @@ impl CommandStore {
         self.assemble(&mut rows)
     }
 
+    /// Fetches the commands matching `filter`
+    pub fn fetch_matching(&self, filter: &ListFilter) -> Result<Vec<Command>> {
+        let mut command_stmt = self
+            .c
+            .prepare(&format!("SELECT * FROM cxd_cmd WHERE {LIST_CLAUSE}"))?;
+        let mut rows = command_stmt.query(Self::list_params(filter))?;
+        self.assemble(&mut rows)
+    }
+
+    /// Binds `filter` to the parameters of [`LIST_CLAUSE`]
+    fn list_params(filter: &ListFilter) -> impl rusqlite::Params + '_ {
+        (
+            &filter.grep,
+            filter.dir.as_ref().and_then(|d| d.to_str()),
+            &filter.env,
+            &filter.tag,
+        )
+    }
+
+    /// Records the start of a run, finished by [`Self::record_finish`] if supervised
+    ///
+    /// # Args
+    /// * `cmd` - Command being run, project commands are recorded by name only
+    /// * `cwd` - Directory `cxd` was invoked from
+    /// * `args` - Arguments passed after the command's name
+    ///
+    /// # Returns
+    /// The `id` of the history entry.
+    pub fn record_start(&self, cmd: &Command, cwd: &Path, args: &[String]) -> Result<i64> {
+        let cmd_id = (cmd.origin == Origin::Cache).then_some(cmd.id);
+        let args = serde_json::to_string(args).map_err(std::io::Error::other)?;
+        Ok(self.c.query_row(
+            "INSERT INTO cxd_history (cmd_id, name, started_at, cwd, args)
+            VALUES (?1, ?2, unixepoch(), ?3, ?4) RETURNING (id)",
+            (cmd_id, &cmd.name, cwd.to_str().unwrap_or_default(), args),
+            |row| row.get("id"),
+        )?)
+    }
+
+    /// Records how a run started by [`Self::record_start`] finished
+    pub fn record_finish(&self, id: i64, duration: Duration, exit_code: i32) -> Result<()> {
+        self.c.execute(
+            "UPDATE cxd_history SET duration_ms = ?1, exit_code = ?2 WHERE id = ?3",
+            (duration.as_millis() as i64, exit_code, id),
+        )?;
+        Ok(())
+    }
+
+    /// Fetches recorded runs matching `filter`, oldest first
+    pub fn fetch_history(&self, filter: &HistoryFilter) -> Result<Vec<Run>> {
+        let mut stmt = self.c.prepare(&format!(
+            "SELECT *, datetime(started_at, 'unixepoch', 'localtime') AS started
+            FROM cxd_history WHERE {HISTORY_CLAUSE} ORDER BY started_at, id"
+        ))?;
+        let runs = stmt
+            .query_map(Self::history_params(filter), |row| Run::try_from(row))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(runs)
+    }
+
+    /// Fetches the most recent run matching `filter`
+    pub fn last_run(&self, filter: &HistoryFilter) -> Result<Option<Run>> {
+        let mut stmt = self.c.prepare(&format!(
+            "SELECT *, datetime(started_at, 'unixepoch', 'localtime') AS started
+            FROM cxd_history WHERE {HISTORY_CLAUSE} ORDER BY started_at DESC, id DESC LIMIT 1"
+        ))?;
+        let run = stmt
+            .query_map(Self::history_params(filter), |row| Run::try_from(row))?
+            .next()
+            .transpose()?;
+        Ok(run)
+    }
+
+    /// Binds `filter` to the parameters of [`HISTORY_CLAUSE`]
+    fn history_params(filter: &HistoryFilter) -> impl rusqlite::Params + '_ {
+        (
+            &filter.name,
+            filter.failed,
+            filter.since,
+            filter.until,
+            filter.cwd.as_ref().and_then(|d| d.to_str()),
+        )
+    }
+
+    /// Converts a time to a unix timestamp
+    ///
+    /// Accepts a unix timestamp, a local date and time understood by SQLite such as
+    /// `2024-09-17 13:00`, or a duration before now such as `30m`, `2h`, `3d` or `1w`.
+    pub fn timestamp(&self, time: &str) -> Result<i64> {
+        let parse_err = || CxdError::ArgumentParse {
+            arg: time.into(),
+            reason: "expected a unix timestamp, date, time or duration such as 2h".into(),
+        };
+        if let Ok(timestamp) = time.parse() {
+            return Ok(timestamp);
+        }
+        let relative = time
+            .char_indices()
+            .last()
+            .and_then(|(i, unit)| Some((time[..i].parse::<i64>().ok()?, unit)))
+            .and_then(|(n, unit)| match unit {
+                's' => Some(format!("-{n} seconds")),
+                'm' => Some(format!("-{n} minutes")),
+                'h' => Some(format!("-{n} hours")),
+                'd' => Some(format!("-{n} days")),
+                'w' => Some(format!("-{} days", n * 7)),
+                _ => None,
+            });
+        let timestamp: Option<i64> = match relative {
+            Some(modifier) => {
+                self.c
+                    .query_row("SELECT unixepoch('now', ?1)", [modifier], |row| row.get(0))?
+            }
+            None => self
+                .c
+                .query_row("SELECT unixepoch(?1, 'utc')", [time], |row| row.get(0))?,
+        };
+        timestamp.ok_or_else(parse_err)
+    }
+
+    /// Begins a transaction which takes the write lock immediately, so concurrent writers wait on
+    /// the busy timeout, rather than failing when upgrading from a read lock
+    fn write_transaction(&self) -> Result<Transaction<'_>> {
+        Ok(Transaction::new_unchecked(
+            &self.c,
+            TransactionBehavior::Immediate,
+        )?)
+    }
+
     /// Assembles a list of row objects into a list of Command objects.
     /// Performs subqueries to fetch rows with a FK to the suppled row.
     fn assemble(&self, rows: &mut rusqlite::Rows<'_>) -> Result<Vec<Command>> {
-        let mut args_stmt = self.c.prepare("SELECT * FROM cxd_arg WHERE cmd_id = ?1")?;
+        let mut args_stmt = self
+            .c
+            .prepare("SELECT * FROM cxd_arg WHERE cmd_id = ?1 ORDER BY position")?;
         let mut envs_stmt = self.c.prepare("SELECT * FROM cxd_env WHERE cmd_id = ?1")?;
+        let mut deps_stmt = self
+            .c
+            .prepare("SELECT * FROM cxd_dep WHERE cmd_id = ?1 ORDER BY position")?;
+        let mut tags_stmt = self.c.prepare(
+            "SELECT cxd_tag.* FROM cxd_tag JOIN cxd_cmd_tag ON tag_id = cxd_tag.id \
+             WHERE cmd_id = ?1 ORDER BY name",
+        )?;
+        let mut steps_stmt = self
+            .c
+            .prepare("SELECT * FROM cxd_step WHERE cmd_id = ?1 ORDER BY position")?;
+        let mut step_args_stmt = self
+            .c
+            .prepare("SELECT * FROM cxd_step_arg WHERE step_id = ?1 ORDER BY position")?;
+        let mut step_envs_stmt = self
+            .c
+            .prepare("SELECT * FROM cxd_step_env WHERE step_id = ?1")?;
 
         let mut ret = vec![];
         while let Some(row) = rows.next()? {
@@ -161,8 +720,56 @@ impl CommandStore {
             while let Some(row) = rows.next()? {
                 envs.push(EnvRow::try_from(row)?);
             }
-            ret.push(Command::new(cmd_row, args, envs));
+            // Fetching associated deps
+            let mut deps = vec![];
+            let mut rows = deps_stmt.query([cmd_row.id])?;
+            while let Some(row) = rows.next()? {
+                deps.push(DepRow::try_from(row)?);
+            }
+
+            // Fetching associated tags
+            let mut tags = vec![];
+            let mut rows = tags_stmt.query([cmd_row.id])?;
+            while let Some(row) = rows.next()? {
+                tags.push(TagRow::try_from(row)?);
+            }
+
+            // Fetching associated steps
+            let mut step_rows = vec![];
+            let mut rows = steps_stmt.query([cmd_row.id])?;
+            while let Some(row) = rows.next()? {
+                step_rows.push(StepRow::try_from(row)?);
+            }
+            let mut steps = vec![];
+            for step_row in step_rows {
+                let mut args = vec![];
+                let mut rows = step_args_stmt.query([step_row.id])?;
+                while let Some(row) = rows.next()? {
+                    args.push(StepArgRow::try_from(row)?);
+                }
+                let mut envs = vec![];
+                let mut rows = step_envs_stmt.query([step_row.id])?;
+                while let Some(row) = rows.next()? {
+                    envs.push(StepEnvRow::try_from(row)?);
+                }
+                steps.push(Step::new(step_row, args, envs));
+            }
+            ret.push(Command::new(cmd_row, args, envs, steps, deps, tags));
         }
         Ok(ret)
     }
 }
+
+impl CommandSource for CommandStore {
+    fn resolve(&self, name: &str, dir: &Path) -> Result<Option<Command>> {
+        CommandStore::resolve(self, name, dir)
+    }
+
+    fn fetch_all(&self) -> Result<Vec<Command>> {
+        CommandStore::fetch_all(self)
+    }
+
+    fn fetch_matching(&self, filter: &ListFilter) -> Result<Vec<Command>> {
+        CommandStore::fetch_matching(self, filter)
+    }
+}
@@ -4,25 +4,10 @@ pub struct CmdRow {
     pub name: String,
     pub cmd: String,
     pub dir: String,
-}
-
-impl CmdRow {
-    pub fn init(c: &rusqlite::Connection) -> rusqlite::Result<()> {
-        c.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS cxd_cmd (
-                id      INTEGER PRIMARY KEY AUTOINCREMENT,
-                name    TEXT NOT NULL,
-                cmd     TEXT NOT NULL,
-                dir     TEXT NOT NULL,
-                UNIQUE(id)
-                UNIQUE(name)
-            )
-        "#,
-            (),
-        )?;
-        Ok(())
-    }
+    pub scope: String,
+    pub shell: Option<String>,
+    pub description: String,
+    pub templated: bool,
 }
 
 impl<'a> TryFrom<&rusqlite::Row<'a>> for CmdRow {
@@ -32,6 +17,19 @@ impl<'a> TryFrom<&rusqlite::Row<'a>> for CmdRow {
         let name: String = row.get("name")?;
         let cmd: String = row.get("cmd")?;
         let dir: String = row.get("dir")?;
-        Ok(Self { id, name, cmd, dir })
+        let scope: String = row.get("scope")?;
+        let shell: Option<String> = row.get("shell")?;
+        let description: String = row.get("description")?;
+        let templated: bool = row.get("templated")?;
+        Ok(Self {
+            id,
+            name,
+            cmd,
+            dir,
+            scope,
+            shell,
+            description,
+            templated,
+        })
     }
 }
@@ -0,0 +1,23 @@
+#[allow(unused)]
+pub struct DepRow {
+    pub id: i64,
+    pub cmd_id: i64,
+    pub position: i64,
+    pub name: String,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for DepRow {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let cmd_id: i64 = row.get("cmd_id")?;
+        let position: i64 = row.get("position")?;
+        let name: String = row.get("name")?;
+        Ok(Self {
+            id,
+            cmd_id,
+            position,
+            name,
+        })
+    }
+}
@@ -6,26 +6,6 @@ pub struct EnvRow {
     pub value: String,
 }
 
-impl EnvRow {
-    pub fn init(c: &rusqlite::Connection) -> rusqlite::Result<()> {
-        c.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS cxd_env (
-                id          INTEGER PRIMARY KEY AUTOINCREMENT,
-                cmd_id      INTEGER NOT NULL,
-                key         TEXT NOT NULL,
-                value       TEXT NOT NULL,
-                UNIQUE(id)
-                FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
-                ON DELETE CASCADE ON UPDATE CASCADE
-            )
-        "#,
-            (),
-        )?;
-        Ok(())
-    }
-}
-
 impl<'a> TryFrom<&rusqlite::Row<'a>> for EnvRow {
     type Error = rusqlite::Error;
     fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
@@ -0,0 +1,328 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+
+use crate::error::{CxdError, Result};
+
+type Migration = fn(&Transaction) -> rusqlite::Result<()>;
+
+/// Schema migrations, where `MIGRATIONS[i]` upgrades a cache from version `i` to `i + 1`
+///
+/// Migrations are append only. Once released, a migration must never change, as caches are
+/// shared across machines running different versions of `cxd`.
+const MIGRATIONS: &[Migration] = &[
+    v1_initial,
+    v2_scopes,
+    v3_arg_positions,
+    v4_history,
+    v5_steps,
+    v6_deps,
+    v7_shell,
+    v8_tags,
+    v9_description,
+    v10_templated,
+];
+
+/// Schema version of caches created by this version of `cxd`
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Upgrades the cache to [`SCHEMA_VERSION`], stored as `PRAGMA user_version`
+///
+/// Before migrating a cache with existing contents, a backup is taken next to it, named
+/// `<path>.v<version>.bak`. All migrations are applied within a single transaction, so a
+/// failure leaves the cache untouched. The transaction takes the write lock before the version
+/// is read again, so processes opening an outdated cache at once wait for the first to migrate
+/// it, rather than each migrating it.
+///
+/// Must be called before foreign keys are enabled, as some migrations rebuild tables.
+///
+/// # Args
+/// * `c` - Connection to the cache
+/// * `path` - Path of the cache, used to place the backup
+pub fn migrate(c: &mut Connection, path: &Path) -> Result<()> {
+    // Most opens find the cache current, without needing the write lock
+    if version(c)? == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = c.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let version = version(&tx)?;
+    if version == SCHEMA_VERSION {
+        // Migrated by another process while waiting on the lock
+        return Ok(());
+    }
+
+    let has_tables: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table'",
+        (),
+        |row| row.get(0),
+    )?;
+    if has_tables {
+        backup(path, version)?;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&tx)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    // Rebuilt tables must still satisfy existing foreign keys
+    let violations: i64 =
+        tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", (), |row| {
+            row.get(0)
+        })?;
+    if violations > 0 {
+        return Err(CxdError::Migration(format!(
+            "{violations} foreign key violations after migrating from version {version}"
+        )));
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Reads the schema version of the cache, failing if it is from a newer `cxd`
+fn version(c: &Connection) -> Result<i64> {
+    let version: i64 = c.query_row("PRAGMA user_version", (), |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(CxdError::CacheVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    Ok(version)
+}
+
+/// Copies the cache to `<path>.v<version>.bak`, replacing any previous backup of that version
+///
+/// `VACUUM` can't run within the migration's transaction, so the copy is read by a connection of
+/// its own. The migration holds the write lock, so it sees the cache as it was before migrating.
+fn backup(path: &Path, version: i64) -> Result<()> {
+    let mut backup = PathBuf::from(path).into_os_string();
+    backup.push(format!(".v{version}.bak"));
+    match std::fs::remove_file(&backup) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e)?,
+        _ => {}
+    }
+    // Unlike copying the file, this includes any contents not yet checkpointed from a WAL
+    let c = Connection::open(path)?;
+    c.execute("VACUUM INTO ?1", [backup.to_str().unwrap_or_default()])?;
+    Ok(())
+}
+
+/// Creates the original tables, which caches from before versioning may already have
+fn v1_initial(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS cxd_cmd (
+            id      INTEGER PRIMARY KEY AUTOINCREMENT,
+            name    TEXT NOT NULL,
+            cmd     TEXT NOT NULL,
+            dir     TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(name)
+        );
+        CREATE TABLE IF NOT EXISTS cxd_arg (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            data        TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS cxd_env (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            key         TEXT NOT NULL,
+            value       TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+    "#,
+    )
+}
+
+/// Adds `cxd_cmd.scope`, replacing UNIQUE(name) with UNIQUE(name, scope)
+///
+/// The constraint can only be dropped by rebuilding the table.
+fn v2_scopes(tx: &Transaction) -> rusqlite::Result<()> {
+    // Caches from before versioning may already be scoped
+    let has_scope: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('cxd_cmd') WHERE name = 'scope'",
+        (),
+        |row| row.get(0),
+    )?;
+    if has_scope {
+        return Ok(());
+    }
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_cmd_new (
+            id      INTEGER PRIMARY KEY AUTOINCREMENT,
+            name    TEXT NOT NULL,
+            cmd     TEXT NOT NULL,
+            dir     TEXT NOT NULL,
+            scope   TEXT NOT NULL DEFAULT '',
+            UNIQUE(id)
+            UNIQUE(name, scope)
+        );
+        INSERT INTO cxd_cmd_new (id, name, cmd, dir) SELECT id, name, cmd, dir FROM cxd_cmd;
+        DROP TABLE cxd_cmd;
+        ALTER TABLE cxd_cmd_new RENAME TO cxd_cmd;
+    "#,
+    )
+}
+
+/// Adds `cxd_arg.position` with UNIQUE(cmd_id, position), so argument order no longer depends
+/// on rowids
+///
+/// Existing args are numbered from 0 within each command, in the rowid order they were read in.
+fn v3_arg_positions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_arg_new (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            data        TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(cmd_id, position)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        INSERT INTO cxd_arg_new (id, cmd_id, position, data)
+            SELECT id, cmd_id, ROW_NUMBER() OVER (PARTITION BY cmd_id ORDER BY id) - 1, data
+            FROM cxd_arg;
+        DROP TABLE cxd_arg;
+        ALTER TABLE cxd_arg_new RENAME TO cxd_arg;
+    "#,
+    )
+}
+
+/// Adds `cxd_history`, recording runs of commands
+///
+/// Runs are kept when their command is removed, so they also store its name.
+fn v4_history(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_history (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER,
+            name        TEXT NOT NULL,
+            started_at  INTEGER NOT NULL,
+            cwd         TEXT NOT NULL,
+            args        TEXT NOT NULL,
+            duration_ms INTEGER,
+            exit_code   INTEGER,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE SET NULL ON UPDATE CASCADE
+        );
+        CREATE INDEX cxd_history_started_at ON cxd_history(started_at);
+    "#,
+    )
+}
+
+/// Adds `cxd_step` along with its args and envs, for commands running several programs in order
+fn v5_steps(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_step (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            cmd         TEXT NOT NULL,
+            dir         TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(cmd_id, position)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE TABLE cxd_step_arg (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            step_id     INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            data        TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(step_id, position)
+            FOREIGN KEY(step_id) REFERENCES cxd_step(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE TABLE cxd_step_env (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            step_id     INTEGER NOT NULL,
+            key         TEXT NOT NULL,
+            value       TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(step_id) REFERENCES cxd_step(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+    "#,
+    )
+}
+
+/// Adds `cxd_dep`, naming the commands to run before a command
+///
+/// Dependencies are stored by name, as they may resolve to project commands or commands added
+/// later.
+fn v6_deps(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_dep (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(cmd_id, position)
+            UNIQUE(cmd_id, name)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+    "#,
+    )
+}
+
+/// Adds `cxd_cmd.shell`, the shell running the command's script, or `NULL` if `cmd` is a program
+///
+/// An empty string selects `$SHELL` at execution time.
+fn v7_shell(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE cxd_cmd ADD COLUMN shell TEXT;")
+}
+
+/// Adds `cxd_tag` and `cxd_cmd_tag`, grouping commands under any number of tags
+///
+/// Tags without commands are deleted along with the last command carrying them.
+fn v8_tags(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE cxd_tag (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            name        TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(name)
+        );
+        CREATE TABLE cxd_cmd_tag (
+            cmd_id      INTEGER NOT NULL,
+            tag_id      INTEGER NOT NULL,
+            PRIMARY KEY(cmd_id, tag_id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+            FOREIGN KEY(tag_id) REFERENCES cxd_tag(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+    "#,
+    )
+}
+
+/// Adds `cxd_cmd.description`, free text notes on the command, empty if none
+fn v9_description(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE cxd_cmd ADD COLUMN description TEXT NOT NULL DEFAULT '';")
+}
+
+/// Adds `cxd_cmd.templated`, whether placeholders are expanded
+///
+/// Commands created before placeholders existed may contain braces meant for the program itself,
+/// such as `awk '{print}'`, so are left unexpanded.
+fn v10_templated(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE cxd_cmd ADD COLUMN templated INTEGER NOT NULL DEFAULT 0;")
+}
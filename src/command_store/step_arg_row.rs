@@ -0,0 +1,23 @@
+#[allow(unused)]
+pub struct StepArgRow {
+    pub id: i64,
+    pub step_id: i64,
+    pub position: i64,
+    pub data: String,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for StepArgRow {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let step_id: i64 = row.get("step_id")?;
+        let position: i64 = row.get("position")?;
+        let data: String = row.get("data")?;
+        Ok(Self {
+            id,
+            step_id,
+            position,
+            data,
+        })
+    }
+}
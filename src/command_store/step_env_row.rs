@@ -0,0 +1,23 @@
+#[allow(unused)]
+pub struct StepEnvRow {
+    pub id: i64,
+    pub step_id: i64,
+    pub key: String,
+    pub value: String,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for StepEnvRow {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let step_id: i64 = row.get("step_id")?;
+        let key: String = row.get("key")?;
+        let value: String = row.get("value")?;
+        Ok(Self {
+            id,
+            step_id,
+            key,
+            value,
+        })
+    }
+}
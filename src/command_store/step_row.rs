@@ -0,0 +1,27 @@
+/// A struct to represent a row of the `cxd_step` table
+#[allow(unused)]
+pub struct StepRow {
+    pub id: i64,
+    pub cmd_id: i64,
+    pub position: i64,
+    pub cmd: String,
+    pub dir: String,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for StepRow {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let cmd_id: i64 = row.get("cmd_id")?;
+        let position: i64 = row.get("position")?;
+        let cmd: String = row.get("cmd")?;
+        let dir: String = row.get("dir")?;
+        Ok(Self {
+            id,
+            cmd_id,
+            position,
+            cmd,
+            dir,
+        })
+    }
+}
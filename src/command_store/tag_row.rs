@@ -0,0 +1,14 @@
+#[allow(unused)]
+pub struct TagRow {
+    pub id: i64,
+    pub name: String,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for TagRow {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let id: i64 = row.get("id")?;
+        let name: String = row.get("name")?;
+        Ok(Self { id, name })
+    }
+}
@@ -0,0 +1,271 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::{Command, Step},
+    error::{CxdError, Result},
+};
+
+/// Version of documents written by this version of `cxd`
+///
+/// Bumped whenever a change would make older `cxd` versions misread a document. Adding optional
+/// fields does not require a bump.
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// Serialization formats for documents
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Guesses the format of a file from its extension, defaulting to JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = CxdError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            _ => Err(CxdError::ArgumentParse {
+                arg: s.into(),
+                reason: "expected one of json, toml".into(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Json => write!(f, "json"),
+            Format::Toml => write!(f, "toml"),
+        }
+    }
+}
+
+/// A versioned collection of commands, as exported and imported
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Document {
+    pub version: u32,
+    #[serde(default)]
+    pub commands: Vec<Entry>,
+}
+
+/// A single command within a [`Document`]
+///
+/// IDs are local to a cache, so are never part of a document. Empty values are omitted.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dir: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "env_map")]
+    pub envs: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Only written for commands whose placeholders aren't expanded
+    #[serde(default = "templated", skip_serializing_if = "is_templated")]
+    pub templated: bool,
+}
+
+fn templated() -> bool {
+    true
+}
+
+fn is_templated(templated: &bool) -> bool {
+    *templated
+}
+
+/// A single step of an [`Entry`], omitting empty values the same way
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepEntry {
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dir: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "env_map")]
+    pub envs: Vec<(String, String)>,
+}
+
+impl From<Command> for Entry {
+    fn from(cmd: Command) -> Self {
+        Self {
+            name: cmd.name,
+            command: cmd.command,
+            shell: cmd.shell,
+            args: cmd.args,
+            dir: cmd.dir.to_str().unwrap_or_default().into(),
+            scope: cmd.scope.to_str().unwrap_or_default().into(),
+            envs: cmd.envs,
+            deps: cmd.deps,
+            steps: cmd.steps.into_iter().map(StepEntry::from).collect(),
+            tags: cmd.tags,
+            description: cmd.description,
+            templated: cmd.templated,
+        }
+    }
+}
+
+impl From<Entry> for Command {
+    fn from(mut entry: Entry) -> Self {
+        entry.tags.sort();
+        entry.tags.dedup();
+        Self {
+            name: entry.name,
+            command: entry.command,
+            shell: entry.shell,
+            args: entry.args,
+            dir: entry.dir.into(),
+            scope: entry.scope.into(),
+            envs: entry.envs,
+            deps: entry.deps,
+            steps: entry.steps.into_iter().map(Step::from).collect(),
+            tags: entry.tags,
+            description: entry.description,
+            templated: entry.templated,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Step> for StepEntry {
+    fn from(step: Step) -> Self {
+        Self {
+            command: step.command,
+            args: step.args,
+            dir: step.dir.to_str().unwrap_or_default().into(),
+            envs: step.envs,
+        }
+    }
+}
+
+impl From<StepEntry> for Step {
+    fn from(entry: StepEntry) -> Self {
+        Self {
+            command: entry.command,
+            args: entry.args,
+            dir: entry.dir.into(),
+            envs: entry.envs,
+        }
+    }
+}
+
+impl Document {
+    pub fn new(cmds: Vec<Command>) -> Self {
+        Self {
+            version: DOCUMENT_VERSION,
+            commands: cmds.into_iter().map(Entry::from).collect(),
+        }
+    }
+
+    /// Serializes the document, always ending with a newline
+    pub fn serialize(&self, format: Format) -> Result<String> {
+        let mut ret = match format {
+            Format::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| CxdError::Document(e.to_string()))?
+            }
+            Format::Toml => toml::to_string(self).map_err(|e| CxdError::Document(e.to_string()))?,
+        };
+        if !ret.ends_with('\n') {
+            ret.push('\n');
+        }
+        Ok(ret)
+    }
+
+    /// Parses a document, refusing versions newer than [`DOCUMENT_VERSION`]
+    pub fn parse(contents: &str, format: Format) -> Result<Self> {
+        // Checking the version first, so a newer document isn't reported as malformed
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let version = match format {
+            Format::Json => serde_json::from_str::<Versioned>(contents).ok(),
+            Format::Toml => toml::from_str::<Versioned>(contents).ok(),
+        };
+        if let Some(Versioned { version: found }) = version {
+            if found > DOCUMENT_VERSION {
+                return Err(CxdError::DocumentVersion {
+                    found,
+                    supported: DOCUMENT_VERSION,
+                });
+            }
+        }
+        match format {
+            Format::Json => {
+                serde_json::from_str(contents).map_err(|e| CxdError::Document(e.to_string()))
+            }
+            Format::Toml => toml::from_str(contents).map_err(|e| CxdError::Document(e.to_string())),
+        }
+    }
+}
+
+/// (De)serializes envs as a map of `KEY = VALUE`, preserving their order
+pub(crate) mod env_map {
+    use std::fmt;
+
+    use serde::{
+        de::{MapAccess, Visitor},
+        ser::SerializeMap,
+        Deserializer, Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(envs: &[(String, String)], s: S) -> Result<S::Ok, S::Error> {
+        let mut map = s.serialize_map(Some(envs.len()))?;
+        for (k, v) in envs {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<(String, String)>, D::Error> {
+        struct EnvVisitor;
+
+        impl<'de> Visitor<'de> for EnvVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map of env variable names to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut envs = vec![];
+                while let Some(pair) = map.next_entry()? {
+                    envs.push(pair);
+                }
+                Ok(envs)
+            }
+        }
+
+        d.deserialize_map(EnvVisitor)
+    }
+}
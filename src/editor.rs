@@ -0,0 +1,257 @@
+use std::{
+    fmt::Write,
+    fs::OpenOptions,
+    io::Write as _,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    command::{Command, Step},
+    error::{CxdError, Result},
+    shell::quote,
+};
+
+const HEADER: &str = r#"# Lines starting with '#' are ignored, and an empty file aborts the edit.
+# Each arg and env is on its own line, values start after the first ': '.
+# Values are quoted the same as a POSIX shell, with spaces in '...' and newlines in $'...'.
+# An empty dir or scope removes it.
+# With a shell, cmd is a script run by that shell, an empty shell uses $SHELL.
+# Each dep names a command to run first, each tag a group the command belongs to.
+# Each desc is a line of the description, taken as is without quotes.
+# Each step starts with its own cmd, followed by its dir, args and envs.
+"#;
+
+/// Serializes a command into the line based format presented to the user's editor
+pub fn serialize(cmd: &Command) -> String {
+    let mut ret = String::from(HEADER);
+    // Empty values are left empty, rather than quoted as ''
+    let value = |v: &str| match v {
+        "" => String::new(),
+        v => quote(v).into_owned(),
+    };
+    let path = |p: &std::path::Path| value(p.to_str().unwrap_or_default());
+    // Writing to a String is infallible
+    let _ = writeln!(ret, "name: {}", quote(&cmd.name));
+    for line in cmd.description.lines() {
+        let _ = writeln!(ret, "desc: {line}");
+    }
+    let _ = writeln!(ret, "scope: {}", path(&cmd.scope));
+    let _ = writeln!(ret, "dir: {}", path(&cmd.dir));
+    if let Some(shell) = &cmd.shell {
+        let _ = writeln!(ret, "shell: {}", value(shell));
+    }
+    let _ = writeln!(ret, "cmd: {}", quote(&cmd.command));
+    for arg in &cmd.args {
+        let _ = writeln!(ret, "arg: {}", quote(arg));
+    }
+    for (k, v) in &cmd.envs {
+        let _ = writeln!(ret, "env: {k}={}", quote(v));
+    }
+    for dep in &cmd.deps {
+        let _ = writeln!(ret, "dep: {}", quote(dep));
+    }
+    for tag in &cmd.tags {
+        let _ = writeln!(ret, "tag: {}", quote(tag));
+    }
+    for step in &cmd.steps {
+        let _ = writeln!(ret, "step: {}", quote(&step.command));
+        if step.dir.components().next().is_some() {
+            let _ = writeln!(ret, "dir: {}", path(&step.dir));
+        }
+        for arg in &step.args {
+            let _ = writeln!(ret, "arg: {}", quote(arg));
+        }
+        for (k, v) in &step.envs {
+            let _ = writeln!(ret, "env: {k}={}", quote(v));
+        }
+    }
+    ret
+}
+
+/// Parses the line based editor format back into a command, keeping the ID and templating of
+/// `original`
+///
+/// # Returns
+/// The parsed command, or `None` if the contents were empty.
+pub fn parse(original: &Command, contents: &str) -> Result<Option<Command>> {
+    let mut cmd = Command {
+        id: original.id,
+        templated: original.templated,
+        ..Default::default()
+    };
+    let mut empty = true;
+    // Lines of the description so far, which may themselves be empty
+    let mut descs = 0;
+    for (i, line) in contents.lines().enumerate() {
+        let err = |reason: &str| CxdError::EditParse {
+            line: i + 1,
+            reason: reason.into(),
+        };
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        empty = false;
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| err("expected <KEY>: <VALUE>"))?;
+        // Prose, so never quoted
+        if key.trim() == "desc" {
+            if !cmd.steps.is_empty() {
+                return Err(err(
+                    "name, scope, cmd, shell, dep, tag and desc must be before the first step",
+                ));
+            }
+            if descs > 0 {
+                cmd.description.push('\n');
+            }
+            cmd.description
+                .push_str(value.strip_prefix(' ').unwrap_or(value));
+            descs += 1;
+            continue;
+        }
+        let mut words = crate::shell::split(value).map_err(|reason| err(&reason))?;
+        if words.len() > 1 {
+            return Err(err(
+                "expected a single value, quote values containing spaces",
+            ));
+        }
+        let value = words.pop().unwrap_or_default();
+        let value = value.as_str();
+        // After a step, dir, arg and env belong to the step
+        let step = cmd.steps.last_mut();
+        match (key.trim(), step) {
+            ("name", None) => cmd.name = value.into(),
+            ("scope", None) => cmd.scope = value.into(),
+            ("dir", None) => cmd.dir = value.into(),
+            ("dir", Some(step)) => step.dir = value.into(),
+            ("cmd", None) => cmd.command = value.into(),
+            ("shell", None) => cmd.shell = Some(value.into()),
+            ("arg", None) => cmd.args.push(value.into()),
+            ("arg", Some(step)) => step.args.push(value.into()),
+            ("env", step) => {
+                let (k, v) = value
+                    .split_once('=')
+                    .ok_or_else(|| err("expected env: <KEY>=<VALUE>"))?;
+                match step {
+                    Some(step) => step.envs.push((k.into(), v.into())),
+                    None => cmd.envs.push((k.into(), v.into())),
+                }
+            }
+            ("dep", None) => cmd.deps.push(value.into()),
+            ("tag", None) => cmd.tags.push(value.into()),
+            ("step", _) if value.is_empty() => return Err(err("step must not be empty")),
+            ("step", _) => cmd.steps.push(Step {
+                command: value.into(),
+                ..Default::default()
+            }),
+            ("name" | "scope" | "cmd" | "shell" | "dep" | "tag", Some(_)) => {
+                return Err(err(
+                    "name, scope, cmd, shell, dep, tag and desc must be before the first step",
+                ))
+            }
+            _ => return Err(err(
+                "unknown key, expected one of name, desc, scope, dir, shell, cmd, arg, env, dep, tag, step",
+            )),
+        }
+    }
+    if empty {
+        return Ok(None);
+    }
+    if cmd.name.is_empty() {
+        return Err(CxdError::Editor("name must not be empty".into()));
+    }
+    if cmd.command.is_empty() {
+        return Err(CxdError::Editor("cmd must not be empty".into()));
+    }
+    cmd.tags.sort();
+    cmd.tags.dedup();
+    cmd.description
+        .truncate(cmd.description.trim_end_matches('\n').len());
+    Ok(Some(cmd))
+}
+
+/// Opens the command in the user's editor, `$VISUAL`, `$EDITOR` or `vi`
+///
+/// # Returns
+/// The edited command, or `None` if the edit was aborted or nothing changed.
+pub fn edit(cmd: &Command) -> Result<Option<Command>> {
+    let editor = std::env::var("VISUAL")
+        .or(std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.is_empty())
+        .unwrap_or("vi".into());
+    let original = serialize(cmd);
+    let file = TempFile::create(&original)?;
+    let path = file.path();
+
+    // Editors are commonly set with arguments, so leave splitting them to the shell
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(path)
+        .status();
+    let contents = std::fs::read_to_string(path);
+    drop(file);
+    let status = status.map_err(|e| CxdError::Exec(editor.clone(), e))?;
+    if !status.success() {
+        return Err(CxdError::Editor(format!("{editor} exited with {status}")));
+    }
+    let contents = contents?;
+    if contents == original {
+        return Ok(None);
+    }
+    parse(cmd, &contents)
+}
+
+/// A file only readable by the user, removed once dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    /// Creates a new file in the temporary directory containing `contents`
+    ///
+    /// The name is never reused, so an existing file or symlink in a shared temporary directory
+    /// can't be written through.
+    fn create(contents: &str) -> Result<Self> {
+        let dir = std::env::temp_dir();
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let mut attempt = 0;
+        loop {
+            let path = dir.join(format!(
+                "cxd-edit-{}-{nanos:x}-{attempt}.txt",
+                std::process::id()
+            ));
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    // Removed on failure once dropped
+                    let ret = Self(path);
+                    file.write_all(contents.as_bytes())?;
+                    return Ok(ret);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -9,6 +9,14 @@ pub enum CxdError {
     #[error("no suitable path found for cache file")]
     CachePath,
 
+    #[error(
+        "cache schema version {found} is from a newer cxd, this cxd supports up to {supported}"
+    )]
+    CacheVersion { found: i64, supported: i64 },
+
+    #[error("failed to migrate cache: {0}")]
+    Migration(String),
+
     #[error("failed to parse cli args: {0}")]
     CliParse(#[from] pico_args::Error),
 
@@ -31,15 +39,99 @@ pub enum CxdError {
     #[error("failed to parse argument \"{arg}\": {reason}")]
     ArgumentParse { arg: String, reason: String },
 
+    #[error("invalid template \"{template}\": {reason}")]
+    InvalidTemplate { template: String, reason: String },
+
+    #[error("unresolved placeholder {placeholder} in \"{name}\": {reason}")]
+    UnresolvedPlaceholder {
+        name: String,
+        placeholder: String,
+        reason: String,
+    },
+
     #[error("command already exists: \"{0}\"")]
     CommandExists(String),
 
-    #[error("command not found: \"{0}\"")]
-    CommandNotFound(String),
+    #[error(
+        "command not found: \"{name}\"{}",
+        crate::fuzzy::did_you_mean(suggestions)
+    )]
+    CommandNotFound {
+        name: String,
+        /// Names of similar commands, best first
+        suggestions: Vec<String>,
+    },
+
+    #[error("command not found: ID {0}")]
+    IdNotFound(i64),
+
+    #[error("no commands tagged \"{0}\"")]
+    TagNotFound(String),
+
+    #[error("command \"{name}\" depends on \"{dep}\", which was not found")]
+    DependencyNotFound { name: String, dep: String },
+
+    #[error("dependency cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+
+    #[error("failed to parse edited command, line {line}: {reason}")]
+    EditParse { line: usize, reason: String },
+
+    #[error("editor: {0}")]
+    Editor(String),
+
+    #[error("invalid document: {0}")]
+    Document(String),
+
+    #[error("document version {found} is from a newer cxd, this cxd supports up to {supported}")]
+    DocumentVersion { found: u32, supported: u32 },
+
+    #[error("invalid project file {}: {reason}", path.display())]
+    ProjectFile {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error("command \"{name}\" is defined in {}, which is read only", path.display())]
+    ReadOnly {
+        name: String,
+        path: std::path::PathBuf,
+    },
+
+    #[error(
+        "{} {}, review its commands and run `cxd --trust{}` to allow running them",
+        path.display(),
+        if *changed { "has changed since it was trusted" } else { "is not trusted" },
+        if path.ends_with(crate::project_file::PROJECT_FILE_NAME) {
+            format!(" {}", path.display())
+        } else {
+            String::new()
+        }
+    )]
+    Untrusted {
+        path: std::path::PathBuf,
+        changed: bool,
+    },
+
+    #[error(
+        "no previous {}run found{}",
+        if *failed { "failed " } else { "" },
+        match dir {
+            Some(dir) => format!(" from {}, use --anywhere to search all directories", dir.display()),
+            None => String::new(),
+        }
+    )]
+    NoPreviousRun {
+        failed: bool,
+        dir: Option<std::path::PathBuf>,
+    },
 
     #[error("failed to read from stdin")]
     Stdin,
 
+    #[error("no terminal to pick from: {0}")]
+    NoTerminal(std::io::Error),
+
     #[error("io: {0}")]
     Io(#[from] std::io::Error),
 
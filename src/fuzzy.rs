@@ -0,0 +1,110 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use crate::error::{CxdError, Result};
+
+/// Most names suggested by a [`CxdError::CommandNotFound`]
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Most names offered by [`choose`]
+const MAX_CHOICES: usize = 9;
+
+/// Ranks `names` by how closely each matches `query`, best first, omitting those which don't
+///
+/// A name matches if `query` is a subsequence of it, such as `bld` of `build`, or if it is within
+/// a small edit distance of `query`, such as `biuld` of `build`. Subsequences rank first, tighter
+/// ones before looser ones, then names by edit distance. Both ignore ASCII case.
+pub fn rank<'a>(query: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let query: Vec<_> = query.chars().map(|c| c.to_ascii_lowercase()).collect();
+    // Allows a typo every few characters
+    let max_distance = (query.len() / 3).max(1);
+    let mut ranked: Vec<_> = names
+        .into_iter()
+        .filter_map(|name| {
+            let chars: Vec<_> = name.chars().map(|c| c.to_ascii_lowercase()).collect();
+            if is_subsequence(&query, &chars) {
+                return Some(((0, chars.len() - query.len()), name));
+            }
+            let distance = distance(&query, &chars);
+            (distance <= max_distance).then_some(((1, distance), name))
+        })
+        .collect();
+    ranked.sort();
+    ranked.dedup_by_key(|(_, name)| *name);
+    ranked.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Checks whether every character of `query` appears in `name`, in order
+fn is_subsequence(query: &[char], name: &[char]) -> bool {
+    let mut name = name.iter();
+    query.iter().all(|q| name.any(|c| c == q))
+}
+
+/// Edit distance, the fewest insertions, deletions, substitutions and swaps of adjacent
+/// characters turning `a` into `b`
+fn distance(a: &[char], b: &[char]) -> usize {
+    // `d[i][j]` is the distance from the first `i` characters of `a` to the first `j` of `b`
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitute = d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = substitute.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Picks which of the ranked `candidates` to run in place of `name`, for `--fuzzy`
+///
+/// A single candidate is picked as is. Otherwise, the user is asked to pick one, if interactive.
+///
+/// # Returns
+/// The picked name, or `None` if there are no candidates, the user declined, or the user could
+/// not be asked.
+pub fn choose<'a>(name: &str, candidates: &'a [String]) -> Result<Option<&'a String>> {
+    match candidates {
+        [] => return Ok(None),
+        [found] => return Ok(Some(found)),
+        _ => {}
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(None);
+    }
+
+    let candidates = &candidates[..candidates.len().min(MAX_CHOICES)];
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stderr, "\"{name}\" not found, did you mean:")?;
+    for (i, candidate) in candidates.iter().enumerate() {
+        writeln!(stderr, "  {}) {candidate}", i + 1)?;
+    }
+    write!(stderr, "Run which? [1-{}, n]: ", candidates.len())?;
+    stderr.flush()?;
+    let response = std::io::stdin()
+        .lock()
+        .lines()
+        .next()
+        .ok_or(CxdError::Stdin)??;
+    Ok(response
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| candidates.get(i.checked_sub(1)?)))
+}
+
+/// Formats the suggestions of a [`CxdError::CommandNotFound`], empty if there are none
+pub fn did_you_mean(suggestions: &[String]) -> String {
+    let quoted: Vec<_> = suggestions.iter().map(|s| format!("\"{s}\"")).collect();
+    match quoted.as_slice() {
+        [] => String::new(),
+        [only] => format!(", did you mean {only}?"),
+        [rest @ .., last] => format!(", did you mean {} or {last}?", rest.join(", ")),
+    }
+}
@@ -0,0 +1,76 @@
+use std::{path::PathBuf, time::Duration};
+
+/// A recorded run of a command, from the `cxd_history` table
+pub struct Run {
+    pub id: i64,
+    /// ID of the cached command which ran, `None` for project commands or once it is removed
+    pub cmd_id: Option<i64>,
+    pub name: String,
+    /// Start time in the local timezone, formatted by SQLite
+    pub started: String,
+    pub cwd: PathBuf,
+    /// Arguments passed after the command's name
+    pub args: Vec<String>,
+    /// `None` if `cxd` was killed before the run finished
+    pub duration: Option<Duration>,
+    /// Exit code, or 128 + N if killed by signal N. `None` the same as `duration`
+    pub exit_code: Option<i32>,
+}
+
+impl<'a> TryFrom<&rusqlite::Row<'a>> for Run {
+    type Error = rusqlite::Error;
+    fn try_from(row: &rusqlite::Row<'a>) -> Result<Self, Self::Error> {
+        let args: String = row.get("args")?;
+        let args = serde_json::from_str(&args).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+        })?;
+        let duration_ms: Option<u64> = row.get("duration_ms")?;
+        let cwd: String = row.get("cwd")?;
+        Ok(Self {
+            id: row.get("id")?,
+            cmd_id: row.get("cmd_id")?,
+            name: row.get("name")?,
+            started: row.get("started")?,
+            cwd: cwd.into(),
+            args,
+            duration: duration_ms.map(Duration::from_millis),
+            exit_code: row.get("exit_code")?,
+        })
+    }
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[{}]: {}", self.id, self.name)?;
+        writeln!(f, "  started: {}", self.started)?;
+        let cwd = self.cwd.to_str().unwrap_or("invalid path");
+        writeln!(f, "  cwd: {}", crate::shell::quote(cwd))?;
+        if !self.args.is_empty() {
+            let args = crate::shell::join(self.args.iter().map(String::as_str));
+            writeln!(f, "  args: {args}")?;
+        }
+        match (self.duration, self.exit_code) {
+            (Some(duration), Some(code)) => {
+                writeln!(f, "  duration: {:.3}s", duration.as_secs_f64())?;
+                write!(f, "  exit: {code}")?;
+            }
+            _ => write!(f, "  exit: unknown")?,
+        }
+        Ok(())
+    }
+}
+
+/// Which runs to fetch with [`crate::command_store::CommandStore::fetch_history`]
+#[derive(Default)]
+pub struct HistoryFilter {
+    /// Only runs of commands with this name
+    pub name: Option<String>,
+    /// Only runs with a non-zero exit code
+    pub failed: bool,
+    /// Only runs started at or after this unix timestamp
+    pub since: Option<i64>,
+    /// Only runs started at or before this unix timestamp
+    pub until: Option<i64>,
+    /// Only runs started from this directory
+    pub cwd: Option<PathBuf>,
+}
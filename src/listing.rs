@@ -0,0 +1,219 @@
+use std::{fmt::Write, path::PathBuf, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{
+    command::{Command, Origin, Step},
+    error::{CxdError, Result},
+    shell,
+};
+
+/// Machine readable formats for `--list`, each with a stable schema
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListFormat {
+    /// An array of [`ListEntry`]
+    Json,
+    /// One command per line, see [`tsv`]
+    Tsv,
+    /// Names only, each followed by a NUL byte
+    Null,
+}
+
+impl FromStr for ListFormat {
+    type Err = CxdError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(ListFormat::Json),
+            "tsv" => Ok(ListFormat::Tsv),
+            "null" => Ok(ListFormat::Null),
+            _ => Err(CxdError::ArgumentParse {
+                arg: s.into(),
+                reason: "expected one of json, tsv, null".into(),
+            }),
+        }
+    }
+}
+
+/// Which commands to fetch with [`crate::command_source::CommandSource::fetch_matching`]
+///
+/// Every set field must match.
+#[derive(Default)]
+pub struct ListFilter {
+    /// Only commands whose name, program, description or one of its args contain this, ignoring
+    /// ASCII case
+    pub grep: Option<String>,
+    /// Only commands run from this absolute directory or one below it
+    pub dir: Option<PathBuf>,
+    /// Only commands setting this env variable
+    pub env: Option<String>,
+    /// Only commands carrying this tag
+    pub tag: Option<String>,
+}
+
+impl ListFilter {
+    /// Checks whether `cmd` matches, the same as
+    /// [`crate::command_store::CommandStore::fetch_matching`] does in SQL
+    pub fn matches(&self, cmd: &Command) -> bool {
+        let contains = |s: &str, pattern: &str| {
+            s.to_ascii_lowercase()
+                .contains(&pattern.to_ascii_lowercase())
+        };
+        self.grep.as_ref().is_none_or(|pattern| {
+            contains(&cmd.name, pattern)
+                || contains(&cmd.command, pattern)
+                || contains(&cmd.description, pattern)
+                || cmd.args.iter().any(|a| contains(a, pattern))
+        }) && self.dir.as_ref().is_none_or(|dir| cmd.dir.starts_with(dir))
+            && self
+                .env
+                .as_ref()
+                .is_none_or(|key| cmd.envs.iter().any(|(k, _)| k == key))
+            && self.tag.as_ref().is_none_or(|tag| cmd.tags.contains(tag))
+    }
+}
+
+/// A listed command, as written by `--list --format json`
+///
+/// Unlike a document [`crate::document::Entry`], no field is ever omitted, unset values are
+/// `null` or empty instead. Fields may be added, but are never renamed or removed.
+#[derive(Serialize)]
+struct ListEntry<'a> {
+    /// `null` for project commands, which have no ID
+    id: Option<i64>,
+    name: &'a str,
+    /// Path of the project file defining the command, `null` for cached commands
+    origin: Option<&'a str>,
+    /// `null` for global commands
+    scope: Option<&'a str>,
+    dir: Option<&'a str>,
+    /// `null` unless `command` is a script, an empty string for `$SHELL`
+    shell: Option<&'a str>,
+    command: &'a str,
+    args: &'a [String],
+    #[serde(serialize_with = "crate::document::env_map::serialize")]
+    envs: &'a [(String, String)],
+    deps: &'a [String],
+    steps: Vec<ListStep<'a>>,
+    tags: &'a [String],
+    /// Empty if none
+    description: &'a str,
+}
+
+/// A step of a [`ListEntry`], with the same conventions
+#[derive(Serialize)]
+struct ListStep<'a> {
+    command: &'a str,
+    args: &'a [String],
+    dir: Option<&'a str>,
+    #[serde(serialize_with = "crate::document::env_map::serialize")]
+    envs: &'a [(String, String)],
+}
+
+/// Converts a path to a string, `None` if empty
+fn path(p: &std::path::Path) -> Option<&str> {
+    p.to_str().filter(|p| !p.is_empty())
+}
+
+impl<'a> From<&'a Command> for ListEntry<'a> {
+    fn from(cmd: &'a Command) -> Self {
+        let origin = match &cmd.origin {
+            Origin::Cache => None,
+            Origin::Project(p) => path(p),
+        };
+        Self {
+            id: (cmd.origin == Origin::Cache).then_some(cmd.id),
+            name: &cmd.name,
+            origin,
+            scope: path(&cmd.scope),
+            dir: path(&cmd.dir),
+            shell: cmd.shell.as_deref(),
+            command: &cmd.command,
+            args: &cmd.args,
+            envs: &cmd.envs,
+            deps: &cmd.deps,
+            steps: cmd.steps.iter().map(ListStep::from).collect(),
+            tags: &cmd.tags,
+            description: &cmd.description,
+        }
+    }
+}
+
+impl<'a> From<&'a Step> for ListStep<'a> {
+    fn from(step: &'a Step) -> Self {
+        Self {
+            command: &step.command,
+            args: &step.args,
+            dir: path(&step.dir),
+            envs: &step.envs,
+        }
+    }
+}
+
+/// Formats commands for `--list --format`, always ending with a newline unless empty
+pub fn list(cmds: &[Command], format: ListFormat) -> Result<String> {
+    Ok(match format {
+        ListFormat::Json => {
+            let entries: Vec<_> = cmds.iter().map(ListEntry::from).collect();
+            let mut ret = serde_json::to_string_pretty(&entries)
+                .map_err(|e| CxdError::Document(e.to_string()))?;
+            ret.push('\n');
+            ret
+        }
+        ListFormat::Tsv => cmds.iter().fold(String::new(), |mut ret, cmd| {
+            // Writing to a String is infallible
+            let _ = writeln!(ret, "{}", tsv(cmd));
+            ret
+        }),
+        ListFormat::Null => cmds.iter().fold(String::new(), |mut ret, cmd| {
+            ret.push_str(&cmd.name);
+            ret.push('\0');
+            ret
+        }),
+    })
+}
+
+/// Formats a command as a line of tab separated fields
+///
+/// The fields are ID (`-` for project commands), name, scope, dir, shell, command, args, envs,
+/// tags and description, with unset values empty and the default shell as `$SHELL`. Args, envs
+/// and tags are each a single field of shell quoted words, the same as `--list`, with envs as
+/// `KEY=VALUE`.
+/// Within every field, backslash, tab, newline and carriage return are escaped as `\\`, `\t`,
+/// `\n` and `\r`.
+fn tsv(cmd: &Command) -> String {
+    let id = match cmd.origin {
+        Origin::Cache => cmd.id.to_string(),
+        Origin::Project(_) => "-".into(),
+    };
+    let envs: Vec<_> = cmd
+        .envs
+        .iter()
+        .map(|(k, v)| format!("{k}={}", shell::quote(v)))
+        .collect();
+    let fields = [
+        id,
+        cmd.name.clone(),
+        path(&cmd.scope).unwrap_or_default().into(),
+        path(&cmd.dir).unwrap_or_default().into(),
+        cmd.shell
+            .as_deref()
+            .map_or("", |s| if s.is_empty() { "$SHELL" } else { s })
+            .into(),
+        cmd.command.clone(),
+        shell::join(cmd.args.iter().map(String::as_str)),
+        envs.join(" "),
+        shell::join(cmd.tags.iter().map(String::as_str)),
+        cmd.description.clone(),
+    ];
+    fields
+        .iter()
+        .map(|f| {
+            f.replace('\\', r"\\")
+                .replace('\t', r"\t")
+                .replace('\n', r"\n")
+                .replace('\r', r"\r")
+        })
+        .collect::<Vec<_>>()
+        .join("\t")
+}
@@ -1,20 +1,55 @@
 use std::{
+    collections::HashSet,
+    ffi::OsStr,
     io::{BufRead, Write},
-    path::PathBuf,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt, process::ExitStatusExt},
+    path::{Path, PathBuf},
 };
 
 mod command;
-use command::Command;
+use command::{Command, Origin, Step};
+
+mod command_source;
+use command_source::{CommandSource, Layered};
 
 mod command_store;
-use command_store::CommandStore;
+use command_store::{CommandStore, Conflict, Imported};
 
 mod cli;
 use cli::{print_long_help, print_op_help, print_short_help, print_version, HelpType};
 
+mod document;
+use document::{Document, Format};
+
+mod editor;
+
 mod error;
 use error::{CxdError, Result};
 
+mod fuzzy;
+
+mod history;
+use history::HistoryFilter;
+
+mod listing;
+use listing::ListFilter;
+
+mod picker;
+use picker::{Action, PickState};
+
+mod project_file;
+use project_file::ProjectFile;
+
+mod shell;
+
+mod supervisor;
+use supervisor::Supervisor;
+
+mod template;
+
+mod trust;
+use trust::TrustStore;
+
 use crate::cli::Op;
 
 fn main() -> Result<()> {
@@ -38,34 +73,74 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let cache_file = cli_args
-        .file
-        .and_then(|s| Some(PathBuf::from(s)))
-        .or(std::env::var("CXD_CACHE_DIR")
-            .or(std::env::var("XDG_CACHE_HOME"))
-            .ok()
-            .and_then(|p| {
-                if p.len() == 0 {
-                    None
-                } else {
-                    Some(PathBuf::from(p).join("cxd.cache"))
-                }
-            }))
+    let default_dir = std::env::var("CXD_CACHE_DIR")
+        .or(std::env::var("XDG_CACHE_HOME"))
+        .ok()
+        .and_then(|p| {
+            if p.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(p))
+            }
+        })
         .or(std::env::var("HOME").ok().and_then(|p| {
-            if p.len() == 0 {
+            if p.is_empty() {
                 None
             } else {
-                Some(PathBuf::from(p).join(".cache").join("cxd.cache"))
+                Some(PathBuf::from(p).join(".cache"))
             }
         }));
 
-    if let None = cache_file {
-        return Err(CxdError::CachePath);
-    }
-    let cache_file = cache_file.unwrap();
+    let cache_file = cli_args
+        .file
+        .map(PathBuf::from)
+        .or(default_dir.as_ref().map(|d| d.join("cxd.cache")))
+        .ok_or(CxdError::CachePath)?;
+
+    // Never next to the cache, which may be foreign
+    let trust_file = std::env::var("CXD_TRUST_FILE")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or(default_dir.map(|d| d.join("cxd.trust")))
+        .ok_or(CxdError::CachePath)?;
 
     let c = CommandStore::new(&cache_file)?;
 
+    // Project commands shadow those of the cache, but are never written to
+    let current_dir = std::env::current_dir()?;
+    let project = ProjectFile::discover(&current_dir)?;
+    let mut sources = Layered::default();
+    if let Some(project) = &project {
+        sources.push(project);
+    }
+    sources.push(&c);
+
+    // Writes by cxd itself keep a trusted cache trusted, and trust newly created caches. The
+    // trust database stays locked until the new fingerprint is trusted, so concurrent writes
+    // can't trust each other's stale fingerprints. The picker locks per change instead, as it
+    // may stay open indefinitely
+    let cache_path = std::fs::canonicalize(&cache_file)?;
+    let mut trust = TrustStore::open(trust_file)?;
+    let writes = matches!(
+        cli_args.op,
+        Some(
+            Op::Add
+                | Op::AddStep
+                | Op::Remove
+                | Op::Edit
+                | Op::Import
+                | Op::Tag
+                | Op::Untag
+                | Op::Rename
+                | Op::Copy
+        )
+    );
+    if writes {
+        trust.lock()?;
+    }
+    let keep_trust = writes && trust.keeps_trust(&cache_path, &c)?;
+
     match cli_args.op {
         Some(Op::Add) => {
             if cli_args.op_args.len() < 2 {
@@ -84,14 +159,29 @@ fn main() -> Result<()> {
             } else if let Some(d) = cli_args.dir {
                 dir = d.into();
             }
+            let mut scope = PathBuf::new();
+            if cli_args.local {
+                scope = std::env::current_dir()?;
+            } else if let Some(s) = cli_args.scope {
+                scope = std::fs::canonicalize(s)?;
+            }
             let mut cmd = Command {
-                id: 0,
                 name: name.clone(),
                 command,
                 args,
                 envs: cli_args.env,
                 dir,
+                scope,
+                shell: cli_args.shell,
+                deps: cli_args.deps,
+                tags: cli_args.tags,
+                description: cli_args.desc.unwrap_or_default(),
+                templated: true,
+                ..Default::default()
             };
+            cmd.tags.sort();
+            cmd.tags.dedup();
+            cmd.validate()?;
             if let Some(id) = c.insert(&cmd)? {
                 cmd.id = id;
                 println!("Created {cmd}");
@@ -99,6 +189,40 @@ fn main() -> Result<()> {
                 return Err(CxdError::CommandExists(name));
             }
         }
+        Some(Op::AddStep) => {
+            if cli_args.op_args.len() < 2 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "add-step".into(),
+                    requires: 2,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let name = &cli_args.op_args[0];
+            let mut cmd = sources
+                .resolve(name, &current_dir)?
+                .ok_or_else(|| not_found(&sources, name, &current_dir))?;
+            ensure_writable(Some(&cmd))?;
+            let mut dir = PathBuf::new();
+            if cli_args.cwd {
+                dir = std::env::current_dir()?;
+            } else if let Some(d) = cli_args.dir {
+                dir = d.into();
+            }
+            cmd.steps.push(Step {
+                command: cli_args.op_args[1].to_owned(),
+                dir,
+                args: cli_args.op_args.split_off(2),
+                envs: cli_args.env,
+            });
+            cmd.validate()?;
+            if !c.update(&cmd)? {
+                return Err(CxdError::CommandNotFound {
+                    name: cmd.name,
+                    suggestions: vec![],
+                });
+            }
+            println!("Updated {cmd}");
+        }
         Some(Op::Remove) => {
             if cli_args.op_args.len() != 1 {
                 return Err(CxdError::WrongArgumentCount {
@@ -108,15 +232,14 @@ fn main() -> Result<()> {
                 });
             }
             let cmd = &cli_args.op_args[0];
-            let res;
-            if cli_args.id {
-                res = c.delete_by_id(cmd.parse().map_err(|_| CxdError::ArgumentParse {
-                    arg: cmd.into(),
-                    reason: "not an integer".into(),
-                })?)?;
-            } else {
-                res = c.delete_by_name(&cmd)?
+            if !cli_args.id {
+                ensure_writable(sources.resolve(cmd, &current_dir)?.as_ref())?;
             }
+            let res = if cli_args.id {
+                c.delete_by_id(parse_id(cmd)?)?
+            } else {
+                c.delete_by_name(cmd, &current_dir)?
+            };
             if let Some(cmd) = res {
                 println!("Removed {}", cmd);
             } else {
@@ -124,12 +247,25 @@ fn main() -> Result<()> {
             }
         }
         Some(Op::List) => {
-            if cli_args.short {
-                for cmd in c.fetch_all()? {
+            let filter = ListFilter {
+                grep: cli_args.grep,
+                dir: if cli_args.here {
+                    Some(current_dir.clone())
+                } else {
+                    cli_args.dir.map(std::fs::canonicalize).transpose()?
+                },
+                env: cli_args.env_key,
+                tag: cli_args.list_tag,
+            };
+            let cmds = sources.fetch_matching(&filter)?;
+            if let Some(format) = cli_args.format {
+                print!("{}", listing::list(&cmds, format.parse()?)?);
+            } else if cli_args.short {
+                for cmd in cmds {
                     println!("{}", cmd.name);
                 }
             } else {
-                for cmd in c.fetch_all()? {
+                for cmd in cmds {
                     println!("{}\n", cmd);
                 }
             }
@@ -143,26 +279,896 @@ fn main() -> Result<()> {
                 .next()
                 .ok_or(CxdError::Stdin)??;
             if response.to_lowercase() == "y" {
+                // Closing first, so the WAL is checkpointed and removed
+                drop(c);
                 std::fs::remove_file(cache_file)?;
+                return Ok(());
+            }
+        }
+        Some(Op::Edit) => {
+            if cli_args.op_args.len() != 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "edit".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let mut cmd = lookup(
+                &c,
+                &sources,
+                &cli_args.op_args[0],
+                &current_dir,
+                cli_args.id,
+            )?;
+            ensure_writable(Some(&cmd))?;
+            let has_flags = cli_args.set_cmd.is_some()
+                || cli_args.set_dir.is_some()
+                || cli_args.set_desc.is_some()
+                || cli_args.set_args.is_some()
+                || !cli_args.add_env.is_empty()
+                || !cli_args.unset_env.is_empty()
+                || !cli_args.add_deps.is_empty()
+                || !cli_args.remove_deps.is_empty();
+            if has_flags {
+                if let Some(command) = cli_args.set_cmd {
+                    cmd.command = command;
+                }
+                if let Some(dir) = cli_args.set_dir {
+                    cmd.dir = dir.into();
+                }
+                if let Some(desc) = cli_args.set_desc {
+                    cmd.description = desc;
+                }
+                if let Some(args) = cli_args.set_args {
+                    cmd.args = args;
+                }
+                cmd.envs.retain(|(k, _)| !cli_args.unset_env.contains(k));
+                for (k, v) in cli_args.add_env {
+                    match cmd.envs.iter_mut().find(|(key, _)| *key == k) {
+                        Some(env) => env.1 = v,
+                        None => cmd.envs.push((k, v)),
+                    }
+                }
+                cmd.deps.retain(|dep| !cli_args.remove_deps.contains(dep));
+                cmd.deps.extend(cli_args.add_deps);
+            } else {
+                match editor::edit(&cmd)? {
+                    Some(edited) => cmd = edited,
+                    None => {
+                        println!("No changes made to {}", cmd.name);
+                        return Ok(());
+                    }
+                }
+            }
+            cmd.validate()?;
+            if !c.update(&cmd)? {
+                return Err(CxdError::CommandNotFound {
+                    name: cmd.name,
+                    suggestions: vec![],
+                });
+            }
+            println!("Updated {cmd}");
+        }
+        Some(Op::Export) => {
+            let format = cli_args
+                .format
+                .map(|f| f.parse())
+                .transpose()?
+                .unwrap_or_default();
+            let cmds = if cli_args.op_args.is_empty() {
+                sources.fetch_all()?
+            } else {
+                cli_args
+                    .op_args
+                    .iter()
+                    .map(|arg| lookup(&c, &sources, arg, &current_dir, cli_args.id))
+                    .collect::<Result<_>>()?
+            };
+            print!("{}", Document::new(cmds).serialize(format)?);
+        }
+        Some(Op::Import) => {
+            if cli_args.op_args.len() != 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "import".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let file = PathBuf::from(&cli_args.op_args[0]);
+            let format = match cli_args.format {
+                Some(f) => f.parse()?,
+                None => Format::from_path(&file),
+            };
+            let contents = if file.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                std::fs::read_to_string(&file)?
+            };
+            let cmds = Document::parse(&contents, format)?
+                .commands
+                .into_iter()
+                .map(Command::from)
+                .collect::<Vec<_>>();
+            for cmd in &cmds {
+                cmd.validate()?;
+            }
+            let conflict = if cli_args.skip_existing {
+                Conflict::Skip
+            } else if cli_args.overwrite {
+                Conflict::Overwrite
+            } else if let Some(suffix) = cli_args.rename_suffix {
+                Conflict::Rename(suffix)
+            } else {
+                Conflict::Fail
+            };
+            let dry_run = cli_args.dry_run;
+            let imported = c.import(cmds, &conflict, dry_run)?;
+            let (mut created, mut skipped, mut overwritten) = (0, 0, 0);
+            for outcome in &imported {
+                match outcome {
+                    Imported::Created(cmd) => {
+                        created += 1;
+                        let verb = if dry_run { "Would create" } else { "Created" };
+                        println!("{verb} {}", cmd.name);
+                    }
+                    Imported::Skipped(cmd) => {
+                        skipped += 1;
+                        let verb = if dry_run { "Would skip" } else { "Skipped" };
+                        println!("{verb} {}, already exists", cmd.name);
+                    }
+                    Imported::Overwritten(cmd) => {
+                        overwritten += 1;
+                        let verb = if dry_run {
+                            "Would overwrite"
+                        } else {
+                            "Overwrote"
+                        };
+                        println!("{verb} {}", cmd.name);
+                    }
+                    Imported::Renamed(cmd, original) => {
+                        created += 1;
+                        let verb = if dry_run { "Would create" } else { "Created" };
+                        println!("{verb} {}, renamed from {original}", cmd.name);
+                    }
+                }
+            }
+            println!("{created} created, {overwritten} overwritten, {skipped} skipped");
+            if dry_run {
+                println!("Dry run, no changes were made");
             }
         }
         // Indicates an execution operation
         None => {
-            if cli_args.op_args.len() != 1 {
+            if cli_args.op_args.is_empty() {
                 return Err(CxdError::WrongArgumentCount {
                     name: "exec".into(),
                     requires: 1,
                     found: cli_args.op_args.len(),
                 });
             }
-            let cmd_name = &cli_args.op_args[0];
-            let cmd = c.get_by_name(cmd_name)?;
-            match cmd {
-                Some(c) => c.exec()?,
-                None => return Err(CxdError::CommandNotFound(cmd_name.into())),
+            let extra_args = cli_args.op_args.split_off(1);
+            let options = ExecOptions {
+                record: cli_args.record,
+                keep_going: cli_args.keep_going,
+                dry_run: cli_args.dry_run,
+                print_shell: cli_args.print_shell,
+                fuzzy: cli_args.fuzzy,
+            };
+            let target = if cli_args.id {
+                Target::Id(parse_id(&cli_args.op_args[0])?)
+            } else {
+                Target::Name(&cli_args.op_args[0])
+            };
+            return execute(
+                c,
+                project.as_ref(),
+                &mut trust,
+                &cache_path,
+                target,
+                extra_args,
+                options,
+            );
+        }
+        Some(Op::Again) => {
+            if !cli_args.op_args.is_empty() {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "again".into(),
+                    requires: 0,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let filter = HistoryFilter {
+                failed: cli_args.failed,
+                cwd: (!cli_args.anywhere).then(|| current_dir.clone()),
+                ..Default::default()
+            };
+            let run = c.last_run(&filter)?.ok_or(CxdError::NoPreviousRun {
+                failed: filter.failed,
+                dir: filter.cwd,
+            })?;
+            let line = std::iter::once(&run.name).chain(&run.args);
+            eprintln!("cxd {}", shell::join(line.map(String::as_str)));
+            // Running as if invoked again from the same directory
+            std::env::set_current_dir(&run.cwd)?;
+            let project = ProjectFile::discover(&run.cwd)?;
+            // Only supervised runs are recorded, so rerunning a failure records whether it passed
+            let options = ExecOptions {
+                record: true,
+                keep_going: cli_args.keep_going,
+                dry_run: cli_args.dry_run,
+                print_shell: cli_args.print_shell,
+                // Runs are recorded by the name which ran
+                fuzzy: false,
+            };
+            // The same cached command, even if renamed or shadowed since
+            let target = match run.cmd_id {
+                Some(id) => Target::Id(id),
+                None => Target::Name(&run.name),
+            };
+            return execute(
+                c,
+                project.as_ref(),
+                &mut trust,
+                &cache_path,
+                target,
+                run.args,
+                options,
+            );
+        }
+        Some(Op::Trust) => {
+            let (path, fingerprint) = match cli_args.op_args.first() {
+                Some(file) => {
+                    let project = ProjectFile::open(std::fs::canonicalize(file)?)?;
+                    (
+                        project.path().to_path_buf(),
+                        project.fingerprint().to_owned(),
+                    )
+                }
+                None => {
+                    // Other writes may otherwise commit after fingerprinting
+                    trust.lock()?;
+                    (cache_path.clone(), trust::cache_fingerprint(&c)?)
+                }
+            };
+            trust.trust(&path, &fingerprint)?;
+            println!("Trusted {}", path.display());
+        }
+        Some(Op::Untrust) => {
+            let path = match cli_args.op_args.first() {
+                Some(file) => std::path::absolute(file)?,
+                None => cache_path.clone(),
+            };
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            if trust.untrust(&path)? {
+                println!("Untrusted {}", path.display());
+            } else {
+                println!("{} was not trusted, nothing was changed", path.display());
+            }
+        }
+        Some(Op::Deps) => {
+            if cli_args.op_args.len() != 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "deps".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
             }
+            let name = &cli_args.op_args[0];
+            let order = sources
+                .resolve_deps(name, &current_dir)?
+                .ok_or_else(|| not_found(&sources, name, &current_dir))?;
+            let root = order.len() - 1;
+            println!("{} [{}]", order[root].name, order.len());
+            print_deps(&order, root, "", &mut vec![root]);
         }
+        Some(op @ (Op::Tag | Op::Untag)) => {
+            if cli_args.op_args.len() < 2 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: if op == Op::Tag { "tag" } else { "untag" }.into(),
+                    requires: 2,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let tags = cli_args.op_args.split_off(1);
+            let name = &cli_args.op_args[0];
+            let mut cmd = sources
+                .resolve(name, &current_dir)?
+                .ok_or_else(|| not_found(&sources, name, &current_dir))?;
+            ensure_writable(Some(&cmd))?;
+            if op == Op::Tag {
+                cmd.tags.extend(tags);
+                cmd.tags.sort();
+                cmd.tags.dedup();
+            } else {
+                cmd.tags.retain(|tag| !tags.contains(tag));
+            }
+            cmd.validate()?;
+            if !c.update(&cmd)? {
+                return Err(CxdError::CommandNotFound {
+                    name: cmd.name,
+                    suggestions: vec![],
+                });
+            }
+            println!("Updated {cmd}");
+        }
+        Some(op @ (Op::Rename | Op::Copy)) => {
+            if cli_args.op_args.len() != 2 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: if op == Op::Rename { "rename" } else { "copy" }.into(),
+                    requires: 2,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let name = &cli_args.op_args[0];
+            let new_name = &cli_args.op_args[1];
+            let mut cmd = sources
+                .resolve(name, &current_dir)?
+                .ok_or_else(|| not_found(&sources, name, &current_dir))?;
+            ensure_writable(Some(&cmd))?;
+            let dir = cli_args.dir.map(std::fs::canonicalize).transpose()?;
+            cmd.name = new_name.clone();
+            if let Some(dir) = &dir {
+                cmd.dir = dir.clone();
+                cmd.scope = dir.clone();
+            }
+            cmd.validate()?;
+            let res = if op == Op::Rename {
+                c.rename(cmd.id, new_name)?
+            } else {
+                c.copy(cmd.id, new_name, dir.as_deref())?
+            };
+            let Some(cmd) = res else {
+                return Err(CxdError::CommandNotFound {
+                    name: name.clone(),
+                    suggestions: vec![],
+                });
+            };
+            if op == Op::Rename {
+                println!("Updated {cmd}");
+            } else {
+                println!("Created {cmd}");
+            }
+        }
+        Some(Op::Show) => {
+            if cli_args.op_args.len() != 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "show".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let cmd = lookup(
+                &c,
+                &sources,
+                &cli_args.op_args[0],
+                &current_dir,
+                cli_args.id,
+            )?;
+            println!("{cmd}");
+        }
+        Some(Op::RunTag) => {
+            if cli_args.op_args.len() != 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "run-tag".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let options = ExecOptions {
+                record: cli_args.record,
+                keep_going: cli_args.keep_going,
+                dry_run: cli_args.dry_run,
+                print_shell: cli_args.print_shell,
+                fuzzy: false,
+            };
+            return execute(
+                c,
+                project.as_ref(),
+                &mut trust,
+                &cache_path,
+                Target::Tag(&cli_args.op_args[0]),
+                vec![],
+                options,
+            );
+        }
+        Some(Op::Pick) => {
+            let mut state = PickState::default();
+            loop {
+                let cmds = picker::candidates(sources.fetch_all()?, &current_dir);
+                let Some(action) = picker::pick(&cmds, &mut state)? else {
+                    break;
+                };
+                let (outcome, keep_trust) = match action {
+                    Action::Run(cmd) => {
+                        eprintln!("cxd {}", shell::quote(&cmd.name));
+                        let options = ExecOptions {
+                            record: false,
+                            keep_going: false,
+                            dry_run: false,
+                            print_shell: false,
+                            fuzzy: false,
+                        };
+                        return execute(
+                            c,
+                            project.as_ref(),
+                            &mut trust,
+                            &cache_path,
+                            Target::Name(&cmd.name),
+                            vec![],
+                            options,
+                        );
+                    }
+                    Action::Edit(cmd) => {
+                        trust.lock()?;
+                        let keep_trust = trust.keeps_trust(&cache_path, &c)?;
+                        (pick_edit(&c, cmd), keep_trust)
+                    }
+                    Action::Delete(cmd) => {
+                        trust.lock()?;
+                        let keep_trust = trust.keeps_trust(&cache_path, &c)?;
+                        (pick_delete(&c, cmd), keep_trust)
+                    }
+                };
+                // Shown in the picker, rather than closing it
+                state.status = Some(outcome.unwrap_or_else(|e| e.to_string()));
+                if keep_trust {
+                    trust.trust(&cache_path, &trust::cache_fingerprint(&c)?)?;
+                }
+                trust.unlock();
+            }
+        }
+        Some(Op::History) => {
+            if cli_args.op_args.len() > 1 {
+                return Err(CxdError::WrongArgumentCount {
+                    name: "history".into(),
+                    requires: 1,
+                    found: cli_args.op_args.len(),
+                });
+            }
+            let filter = HistoryFilter {
+                name: cli_args.op_args.pop(),
+                failed: cli_args.failed,
+                since: cli_args.since.map(|t| c.timestamp(&t)).transpose()?,
+                until: cli_args.until.map(|t| c.timestamp(&t)).transpose()?,
+                cwd: None,
+            };
+            for run in c.fetch_history(&filter)? {
+                println!("{}\n", run);
+            }
+        }
+    }
+
+    if keep_trust {
+        trust.trust(&cache_path, &trust::cache_fingerprint(&c)?)?;
     }
 
     Ok(())
 }
+
+/// How [`execute`] runs a command
+struct ExecOptions {
+    /// Supervises the command, also enabled by `$CXD_RECORD`
+    record: bool,
+    /// Runs the remaining steps after a step fails
+    keep_going: bool,
+    /// Prints what would run instead, see [`print_dry_run`]
+    dry_run: bool,
+    /// Prints what would run as a shell script instead, see [`print_shell`]
+    print_shell: bool,
+    /// Runs a similar command if none is named `name`, see [`fuzzy::choose`]
+    fuzzy: bool,
+}
+
+/// What [`execute`] runs, resolved from the CWD
+enum Target<'a> {
+    /// The command with this name
+    Name(&'a str),
+    /// The cached command with this ID, whatever its scope
+    Id(i64),
+    /// Every command with this tag, see [`CommandSource::fetch_tagged`]
+    Tag(&'a str),
+}
+
+/// A command to run, along with the dependencies to run before it
+struct Planned {
+    deps: Vec<Command>,
+    cmd: Command,
+}
+
+/// Executes commands resolved from the CWD, after checking their files are trusted, recording
+/// each run in the history
+///
+/// Unless supervised, replaces the current process. Otherwise, runs each planned command in turn,
+/// waiting on each of its dependencies, then the command and each of its steps. A failure stops
+/// the run, which exits the same way as the first which failed, or the last. Commands with steps
+/// or dependencies, and tags, are always supervised. With `--dry-run` or `--print-shell`, only
+/// prints what would run, without checking trust or recording.
+///
+/// # Args
+/// * `c` - Cache to resolve from and record in, closed before exiting
+/// * `project` - Project file of the CWD, shadowing the cache
+/// * `trust` - Trust database, updated if the user trusts the command's file
+/// * `cache_path` - Canonical path of the cache file
+/// * `target` - What to run
+/// * `extra_args` - Runtime arguments passed after the command name
+/// * `options` - How to run the command
+fn execute(
+    c: CommandStore,
+    project: Option<&ProjectFile>,
+    trust: &mut TrustStore,
+    cache_path: &Path,
+    target: Target,
+    extra_args: Vec<String>,
+    options: ExecOptions,
+) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let mut sources = Layered::default();
+    if let Some(project) = project {
+        sources.push(project);
+    }
+    sources.push(&c);
+    let mut plan = match target {
+        Target::Name(name) => vec![plan_name(
+            &sources,
+            name,
+            &current_dir,
+            &extra_args,
+            &options,
+        )?],
+        Target::Id(id) => {
+            let cmd = c.get_by_id(id)?.ok_or(CxdError::IdNotFound(id))?;
+            let mut deps = sources.order_deps(cmd, &current_dir)?;
+            let cmd = deps.pop().expect("found command is ordered last");
+            vec![Planned { deps, cmd }]
+        }
+        Target::Tag(tag) => plan_tag(&sources, tag, &current_dir)?,
+    };
+    let is_tag = matches!(target, Target::Tag(_));
+
+    // Nothing runs, so reviewing a command needs no trust
+    if options.dry_run || options.print_shell {
+        let mut processes = vec![];
+        for Planned { deps, cmd } in plan {
+            let runs = deps
+                .into_iter()
+                .map(|dep| dep.render(vec![]))
+                .chain([cmd.render(extra_args.clone())]);
+            for run in runs {
+                let run = run?;
+                for (i, process) in run.processes().into_iter().enumerate() {
+                    let label = match i {
+                        0 => run.name.clone(),
+                        i => format!("{} step {i}", run.name),
+                    };
+                    processes.push((label, process));
+                }
+            }
+        }
+        if options.print_shell {
+            print_shell(&processes);
+        } else {
+            print_dry_run(&processes, &current_dir);
+        }
+        return Ok(());
+    }
+
+    let all = || plan.iter().flat_map(|p| p.deps.iter().chain([&p.cmd]));
+    if let Some(project) = project {
+        if all().any(|c| matches!(c.origin, Origin::Project(_))) {
+            trust.ensure(project.path(), project.fingerprint(), project)?;
+        }
+    }
+    if all().any(|c| c.origin == Origin::Cache) {
+        trust.ensure(cache_path, &trust::cache_fingerprint(&c)?, &c)?;
+    }
+
+    let record =
+        options.record || std::env::var("CXD_RECORD").is_ok_and(|v| !v.is_empty() && v != "0");
+    if !record && !is_tag && plan[0].cmd.steps.is_empty() && plan[0].deps.is_empty() {
+        let Planned { cmd, .. } = plan.remove(0);
+        return cmd.exec(extra_args);
+    }
+
+    let mut last = None;
+    for Planned { deps, cmd } in plan {
+        if is_tag {
+            eprintln!("cxd {}", shell::quote(&cmd.name));
+        }
+        // Dependencies never see the extra arguments, which are meant for the command itself
+        let deps = deps
+            .into_iter()
+            .map(|dep| dep.render(vec![]))
+            .collect::<Result<Vec<_>>>()?;
+        let cmd = cmd.render(extra_args.clone())?;
+        let id = c.record_start(&cmd, &current_dir, &extra_args)?;
+        let start = std::time::Instant::now();
+        let mut failed = None;
+        let mut status = None;
+        let runs = deps.iter().map(|dep| (dep, false)).chain([(&cmd, true)]);
+        'run: for (run, is_cmd) in runs {
+            for process in run.processes() {
+                let program = process.get_program().to_string_lossy().into_owned();
+                let child = match Supervisor::spawn(process) {
+                    Ok(child) => child,
+                    Err(e) => {
+                        // Same as a shell failing to find a program
+                        c.record_finish(id, start.elapsed(), 127)?;
+                        return Err(CxdError::Exec(program, e));
+                    }
+                };
+                let finished = child.wait()?;
+                status = Some(finished);
+                if !finished.success() {
+                    failed.get_or_insert(finished);
+                    // Only the command's own steps keep going, and signals, such as ^C, stop the
+                    // run
+                    if !is_cmd || !options.keep_going || finished.signal().is_some() {
+                        break 'run;
+                    }
+                }
+            }
+        }
+        let Some(status) = failed.or(status) else {
+            continue;
+        };
+        c.record_finish(id, start.elapsed(), supervisor::exit_code(status))?;
+        last = Some(status);
+        // Later commands of a tag never run after a failure
+        if failed.is_some() {
+            break;
+        }
+    }
+    let Some(status) = last else {
+        return Ok(());
+    };
+    // Closing first, as exiting skips destructors
+    drop(sources);
+    drop(c);
+    supervisor::exit_as(status);
+}
+
+/// Plans running the command named `name` from `current_dir`, or a similar one with `--fuzzy`
+fn plan_name(
+    sources: &dyn CommandSource,
+    name: &str,
+    current_dir: &Path,
+    extra_args: &[String],
+    options: &ExecOptions,
+) -> Result<Planned> {
+    let mut deps = match sources.resolve_deps(name, current_dir)? {
+        Some(deps) => deps,
+        None if options.fuzzy => {
+            let candidates = sources.suggest(name, current_dir)?;
+            let Some(found) = fuzzy::choose(name, &candidates)? else {
+                return Err(not_found(sources, name, current_dir));
+            };
+            let line = std::iter::once(found).chain(extra_args);
+            eprintln!("cxd {}", shell::join(line.map(String::as_str)));
+            sources
+                .resolve_deps(found, current_dir)?
+                .ok_or_else(|| not_found(sources, found, current_dir))?
+        }
+        None => return Err(not_found(sources, name, current_dir)),
+    };
+    let cmd = deps.pop().expect("resolved command is ordered last");
+    Ok(Planned { deps, cmd })
+}
+
+/// Plans running every command tagged `tag` from `current_dir`, in order
+///
+/// Each command runs at most once, so a dependency shared by several tagged commands runs before
+/// the first of them only, and a tagged command already run as a dependency is not run again.
+fn plan_tag(sources: &dyn CommandSource, tag: &str, current_dir: &Path) -> Result<Vec<Planned>> {
+    let tagged = sources.fetch_tagged(tag, current_dir)?;
+    if tagged.is_empty() {
+        return Err(CxdError::TagNotFound(tag.into()));
+    }
+    let mut plan = vec![];
+    let mut seen = HashSet::new();
+    for cmd in tagged {
+        let order = sources
+            .resolve_deps(&cmd.name, current_dir)?
+            .ok_or_else(|| not_found(sources, &cmd.name, current_dir))?;
+        let mut deps: Vec<_> = order
+            .into_iter()
+            .filter(|c| seen.insert(c.name.clone()))
+            .collect();
+        // Every dependency has run already if the command has
+        if let Some(cmd) = deps.pop() {
+            plan.push(Planned { deps, cmd });
+        }
+    }
+    Ok(plan)
+}
+
+/// Edits a command picked with `--pick` in the user's editor
+///
+/// # Returns
+/// What was done, to show in the picker.
+fn pick_edit(c: &CommandStore, cmd: Command) -> Result<String> {
+    ensure_writable(Some(&cmd))?;
+    let Some(edited) = editor::edit(&cmd)? else {
+        return Ok(format!("No changes made to {}", cmd.name));
+    };
+    edited.validate()?;
+    if !c.update(&edited)? {
+        return Err(CxdError::CommandNotFound {
+            name: edited.name,
+            suggestions: vec![],
+        });
+    }
+    Ok(format!("Updated {}", edited.name))
+}
+
+/// Deletes a command picked with `--pick`
+///
+/// # Returns
+/// What was done, to show in the picker.
+fn pick_delete(c: &CommandStore, cmd: Command) -> Result<String> {
+    ensure_writable(Some(&cmd))?;
+    match c.delete_by_id(cmd.id)? {
+        Some(cmd) => Ok(format!("Removed {}", cmd.name)),
+        None => Err(CxdError::CommandNotFound {
+            name: cmd.name,
+            suggestions: vec![],
+        }),
+    }
+}
+
+/// Parses a command ID given with `--id`
+fn parse_id(arg: &str) -> Result<i64> {
+    arg.parse().map_err(|_| CxdError::ArgumentParse {
+        arg: arg.into(),
+        reason: "not an integer".into(),
+    })
+}
+
+/// Finds the command named `arg` as it resolves from `dir`, or whose ID is `arg` with `--id`
+fn lookup(
+    c: &CommandStore,
+    sources: &dyn CommandSource,
+    arg: &str,
+    dir: &Path,
+    by_id: bool,
+) -> Result<Command> {
+    if by_id {
+        let id = parse_id(arg)?;
+        c.get_by_id(id)?.ok_or(CxdError::IdNotFound(id))
+    } else {
+        sources
+            .resolve(arg, dir)?
+            .ok_or_else(|| not_found(sources, arg, dir))
+    }
+}
+
+/// Builds the error for a command not found from `dir`, suggesting similar names
+fn not_found(source: &dyn CommandSource, name: &str, dir: &Path) -> CxdError {
+    match source.suggest(name, dir) {
+        Ok(mut suggestions) => {
+            suggestions.truncate(fuzzy::MAX_SUGGESTIONS);
+            CxdError::CommandNotFound {
+                name: name.into(),
+                suggestions,
+            }
+        }
+        Err(e) => e,
+    }
+}
+
+/// Prints each process which would run, labeled, as resolved for execution
+///
+/// # Args
+/// * `processes` - Processes to print, labeled with the command or step they belong to
+/// * `current_dir` - Directory processes without their own run in
+fn print_dry_run(processes: &[(String, std::process::Command)], current_dir: &Path) {
+    for (i, (label, process)) in processes.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let dir = process.get_current_dir().unwrap_or(current_dir);
+        println!("{label}");
+        println!("  dir: {}", dir.display());
+        let mut path = std::env::var_os("PATH");
+        for (k, v) in process.get_envs() {
+            let Some(v) = v else { continue };
+            println!("  env: {}={}", k.to_string_lossy(), v.to_string_lossy());
+            if k == "PATH" {
+                path = Some(v.to_owned());
+            }
+        }
+        let program = process.get_program();
+        match find_program(program, path.as_deref(), dir) {
+            Some(found) => println!("  program: {}", found.display()),
+            None => println!("  program: {} (not found)", program.to_string_lossy()),
+        }
+        println!("  argv: {}", argv(process));
+    }
+}
+
+/// Prints each process which would run as a line of a POSIX shell script, joined by `&&`
+fn print_shell(processes: &[(String, std::process::Command)]) {
+    let lines: Vec<_> = processes
+        .iter()
+        .map(|(_, process)| {
+            let envs = process.get_envs().filter_map(|(k, v)| {
+                let v = v?.to_string_lossy();
+                Some(format!("{}={} ", k.to_string_lossy(), shell::quote(&v)))
+            });
+            let line = format!("{}{}", envs.collect::<String>(), argv(process));
+            match process.get_current_dir() {
+                // A subshell, so pasting the line leaves the shell's directory alone
+                Some(dir) => format!("(cd {} && {line})", shell::quote(&dir.to_string_lossy())),
+                None => line,
+            }
+        })
+        .collect();
+    println!("{}", lines.join(" &&\n"));
+}
+
+/// Quoted program and arguments of a process
+fn argv(process: &std::process::Command) -> String {
+    let words: Vec<_> = std::iter::once(process.get_program())
+        .chain(process.get_args())
+        .map(OsStr::to_string_lossy)
+        .collect();
+    shell::join(words.iter().map(|w| w.as_ref()))
+}
+
+/// Finds the file `program` would run from `dir`, searching `path` for bare names the same as
+/// `execvp`
+fn find_program(program: &OsStr, path: Option<&OsStr>, dir: &Path) -> Option<PathBuf> {
+    let is_executable = |p: &Path| {
+        p.metadata()
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    };
+    if program.as_bytes().contains(&b'/') {
+        let found = dir.join(program);
+        return is_executable(&found).then_some(found);
+    }
+    std::env::split_paths(path?)
+        .map(|p| dir.join(p).join(program))
+        .find(|p| is_executable(p))
+}
+
+/// Prints the dependencies of `order[i]` as branches of a tree, numbered by the order they run in
+///
+/// # Args
+/// * `order` - Commands in the order they run, from [`CommandSource::resolve_deps`]
+/// * `prefix` - Printed before each branch, continuing the branches of parents
+/// * `shown` - Indices already printed, whose dependencies are not repeated
+fn print_deps(order: &[Command], i: usize, prefix: &str, shown: &mut Vec<usize>) {
+    let deps: Vec<_> = order[i]
+        .deps
+        .iter()
+        .filter_map(|dep| order.iter().position(|c| c.name == *dep))
+        .collect();
+    for (n, &dep) in deps.iter().enumerate() {
+        let (branch, indent) = if n + 1 == deps.len() {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        if shown.contains(&dep) {
+            println!("{prefix}{branch}{} [{}] (*)", order[dep].name, dep + 1);
+            continue;
+        }
+        shown.push(dep);
+        println!("{prefix}{branch}{} [{}]", order[dep].name, dep + 1);
+        print_deps(order, dep, &format!("{prefix}{indent}"), shown);
+    }
+}
+
+/// Fails if the command is from a read-only project file
+fn ensure_writable(cmd: Option<&Command>) -> Result<()> {
+    match cmd {
+        Some(Command {
+            name,
+            origin: Origin::Project(path),
+            ..
+        }) => Err(CxdError::ReadOnly {
+            name: name.clone(),
+            path: path.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
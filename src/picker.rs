@@ -0,0 +1,386 @@
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs::File,
+    io::{Read, Write},
+    os::fd::AsRawFd,
+    path::Path,
+};
+
+use crate::{
+    command::Command,
+    error::{CxdError, Result},
+    fuzzy, shell,
+};
+
+/// How long to wait for the rest of an escape sequence, before taking ESC as a key of its own
+const ESCAPE_TIMEOUT_MS: libc::c_int = 25;
+
+/// How often to check for a resized terminal while waiting for a key
+const RESIZE_POLL_MS: libc::c_int = 250;
+
+/// What the user picked a command for
+pub enum Action {
+    Run(Command),
+    Edit(Command),
+    Delete(Command),
+}
+
+/// State of the picker, kept while it is closed to edit or delete a command, so it reopens
+/// where it was left
+#[derive(Default)]
+pub struct PickState {
+    /// Filters commands by name, the same as `--fuzzy`
+    pub query: String,
+    /// Name of the selected command, the best match if `None`
+    pub selected: Option<String>,
+    /// Shown in place of the key hints until the next key, such as the outcome of an edit
+    pub status: Option<String>,
+    /// Whether keys are commands, such as `e`, rather than typed into the query
+    keys: bool,
+}
+
+/// A key read from the terminal
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Tab,
+    Up,
+    Down,
+    Esc,
+    /// A control character, by its letter
+    Ctrl(char),
+    /// Unrecognized escape sequences
+    Other,
+}
+
+/// Orders commands for picking from `dir`, leaving out those which would not resolve from it
+///
+/// Commands scoped nearest to `dir` come first, then global commands run from within `dir`, then
+/// all other global commands, each by name.
+pub fn candidates(cmds: Vec<Command>, dir: &Path) -> Vec<Command> {
+    let depth = |c: &Command| Reverse(c.scope.components().count());
+    let mut cmds: Vec<_> = cmds.into_iter().filter(|c| c.in_scope(dir)).collect();
+    // Only the nearest scope of each name resolves
+    cmds.sort_by_key(depth);
+    let mut seen = HashSet::new();
+    cmds.retain(|c| seen.insert(c.name.clone()));
+    cmds.sort_by_cached_key(|c| (depth(c), !c.dir.starts_with(dir), c.name.clone()));
+    cmds
+}
+
+/// Opens a full screen picker over `cmds` on the controlling terminal, until a command is picked
+///
+/// # Returns
+/// The picked command along with what to do with it, or `None` if the user quit.
+pub fn pick(cmds: &[Command], state: &mut PickState) -> Result<Option<Action>> {
+    let mut term = Terminal::open()?;
+    loop {
+        let matches = filter(cmds, &state.query);
+        let selected = state
+            .selected
+            .as_ref()
+            .and_then(|name| matches.iter().position(|c| c.name == *name))
+            .unwrap_or(0);
+        term.render(state, &matches, selected)?;
+        let key = loop {
+            if let Some(key) = term.read_key()? {
+                break key;
+            }
+            if term.resized() {
+                term.render(state, &matches, selected)?;
+            }
+        };
+        state.status = None;
+        let cmd = matches.get(selected).copied();
+        let mut select = |i: usize| {
+            if let Some(c) = matches.get(i) {
+                state.selected = Some(c.name.clone());
+            }
+        };
+        match (key, state.keys) {
+            (Key::Esc | Key::Ctrl('c'), _) | (Key::Char('q'), true) => return Ok(None),
+            (Key::Enter, _) => match cmd {
+                Some(cmd) => return Ok(Some(Action::Run(cmd.clone()))),
+                None => continue,
+            },
+            (Key::Tab, _) => state.keys = !state.keys,
+            (Key::Up | Key::Ctrl('p'), _) | (Key::Char('k'), true) => {
+                select(selected.saturating_sub(1))
+            }
+            (Key::Down | Key::Ctrl('n'), _) | (Key::Char('j'), true) => select(selected + 1),
+            (Key::Char('/'), true) => state.keys = false,
+            (Key::Ctrl('e'), _) | (Key::Char('e'), true) => {
+                if let Some(cmd) = cmd {
+                    return Ok(Some(Action::Edit(cmd.clone())));
+                }
+            }
+            (Key::Ctrl('d'), _) | (Key::Char('d'), true) => {
+                let Some(cmd) = cmd else { continue };
+                state.status = Some(format!("Delete \"{}\"? [yn]", cmd.name));
+                term.render(state, &matches, selected)?;
+                state.status = None;
+                if let Key::Char('y' | 'Y') = term.wait_key()? {
+                    return Ok(Some(Action::Delete(cmd.clone())));
+                }
+            }
+            (Key::Char(c), false) => {
+                state.query.push(c);
+                state.selected = None;
+            }
+            (Key::Backspace, false) => {
+                state.query.pop();
+                state.selected = None;
+            }
+            (Key::Ctrl('u'), false) => {
+                state.query.clear();
+                state.selected = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Commands matching `query`, best first, or all in their given order if `query` is empty
+fn filter<'a>(cmds: &'a [Command], query: &str) -> Vec<&'a Command> {
+    if query.is_empty() {
+        return cmds.iter().collect();
+    }
+    fuzzy::rank(query, cmds.iter().map(|c| c.name.as_str()))
+        .into_iter()
+        .filter_map(|name| cmds.iter().find(|c| c.name == name))
+        .collect()
+}
+
+/// Truncates `line` to `width` characters, replacing control characters
+fn fit(line: &str, width: usize) -> String {
+    line.chars()
+        .map(|c| if c.is_control() { '?' } else { c })
+        .take(width)
+        .collect()
+}
+
+/// The controlling terminal, in raw mode on the alternate screen until dropped
+struct Terminal {
+    tty: File,
+    original: libc::termios,
+    size: (usize, usize),
+}
+
+impl Terminal {
+    /// Opens `/dev/tty`, so the picker works even if stdin or stdout are redirected
+    fn open() -> Result<Self> {
+        let tty = File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(CxdError::NoTerminal)?;
+        let fd = tty.as_raw_fd();
+        // SAFETY: termios is zeroable, and fully initialized by tcgetattr before use
+        let original = unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut original) != 0 {
+                return Err(CxdError::NoTerminal(std::io::Error::last_os_error()));
+            }
+            original
+        };
+        let mut raw = original;
+        // Keys arrive one at a time, unechoed, with ^C read as a key rather than a signal
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // Applied now rather than after flushing, keeping keys typed ahead
+        // SAFETY: fd is open for the lifetime of `tty`
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut term = Self {
+            tty,
+            original,
+            size: (0, 0),
+        };
+        // Alternate screen, with the cursor hidden
+        term.tty.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        term.size = term.query_size();
+        Ok(term)
+    }
+
+    /// Rows and columns of the terminal, 24 by 80 if unknown
+    fn query_size(&self) -> (usize, usize) {
+        // SAFETY: winsize is zeroable, and only read if filled in by the ioctl
+        unsafe {
+            let mut size: libc::winsize = std::mem::zeroed();
+            if libc::ioctl(self.tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) == 0
+                && size.ws_row > 0
+                && size.ws_col > 0
+            {
+                (size.ws_row.into(), size.ws_col.into())
+            } else {
+                (24, 80)
+            }
+        }
+    }
+
+    /// Checks whether the terminal was resized since last checked
+    fn resized(&mut self) -> bool {
+        let size = self.query_size();
+        std::mem::replace(&mut self.size, size) != size
+    }
+
+    /// Checks whether input is ready within `timeout_ms`
+    fn poll(&self, timeout_ms: libc::c_int) -> Result<bool> {
+        let mut fds = libc::pollfd {
+            fd: self.tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: fds is a single valid pollfd
+        match unsafe { libc::poll(&mut fds, 1, timeout_ms) } {
+            -1 => match std::io::Error::last_os_error() {
+                // Interrupted, such as by SIGWINCH
+                e if e.kind() == std::io::ErrorKind::Interrupted => Ok(false),
+                e => Err(e.into()),
+            },
+            n => Ok(n > 0),
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let mut byte = [0];
+        if self.tty.read(&mut byte)? == 0 {
+            return Err(CxdError::Stdin);
+        }
+        Ok(byte[0])
+    }
+
+    /// Reads a key, or `None` if none was pressed for a while
+    fn read_key(&mut self) -> Result<Option<Key>> {
+        if !self.poll(RESIZE_POLL_MS)? {
+            return Ok(None);
+        }
+        let byte = self.read_byte()?;
+        Ok(Some(match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            0x1b => {
+                if !self.poll(ESCAPE_TIMEOUT_MS)? {
+                    return Ok(Some(Key::Esc));
+                }
+                // Arrow keys are sent as ESC [ or ESC O, followed by a letter
+                match self.read_byte()? {
+                    b'[' | b'O' => match self.read_byte()? {
+                        b'A' => Key::Up,
+                        b'B' => Key::Down,
+                        _ => Key::Other,
+                    },
+                    _ => Key::Other,
+                }
+            }
+            0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+            0x00..=0x1f => Key::Other,
+            0x20..=0x7e => Key::Char(byte as char),
+            _ => {
+                // The rest of a UTF-8 character, by the number of leading ones
+                let len = byte.leading_ones() as usize;
+                let mut buf = vec![byte];
+                for _ in 1..len.min(4) {
+                    buf.push(self.read_byte()?);
+                }
+                match std::str::from_utf8(&buf)
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                {
+                    Some(c) => Key::Char(c),
+                    None => Key::Other,
+                }
+            }
+        }))
+    }
+
+    /// Waits as long as it takes for a key
+    fn wait_key(&mut self) -> Result<Key> {
+        loop {
+            if let Some(key) = self.read_key()? {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// Draws the query, the list of matches with the selected one highlighted, a preview of the
+    /// selected command, and the key hints or status
+    fn render(&mut self, state: &PickState, matches: &[&Command], selected: usize) -> Result<()> {
+        let (rows, cols) = self.size;
+        // The query, separator and hints take a row each, the rest is split between list and preview
+        let list_rows = (rows.saturating_sub(3) / 2).max(1);
+        let preview_rows = rows.saturating_sub(3 + list_rows);
+        let mut out = String::from("\x1b[H");
+        let line = |out: &mut String, text: &str| {
+            out.push_str(text);
+            out.push_str("\x1b[K\r\n");
+        };
+
+        let count = format!("{}/{}", matches.len().min(selected + 1), matches.len());
+        let prompt = if state.keys { "keys" } else { "filter" };
+        line(
+            &mut out,
+            &fit(&format!("{prompt}> {}  {count}", state.query), cols),
+        );
+
+        // Scrolled so the selected command is visible
+        let offset = (selected + 1).saturating_sub(list_rows);
+        for i in offset..offset + list_rows {
+            let Some(cmd) = matches.get(i) else {
+                line(&mut out, "");
+                continue;
+            };
+            let program = std::iter::once(&cmd.command).chain(&cmd.args);
+            let program = shell::join(program.map(String::as_str));
+            let name = fit(&cmd.name, cols);
+            let rest = fit(
+                &format!("  {program}"),
+                cols.saturating_sub(name.chars().count()),
+            );
+            if i == selected {
+                line(
+                    &mut out,
+                    &format!("\x1b[7m{name}\x1b[27m\x1b[2m{rest}\x1b[0m"),
+                );
+            } else {
+                line(&mut out, &format!("{name}\x1b[2m{rest}\x1b[0m"));
+            }
+        }
+        line(&mut out, &"─".repeat(cols));
+
+        let preview = matches
+            .get(selected)
+            .map(|cmd| cmd.to_string())
+            .unwrap_or_default();
+        let mut preview = preview.lines();
+        for _ in 0..preview_rows {
+            line(&mut out, &fit(preview.next().unwrap_or_default(), cols));
+        }
+
+        let hints = match (&state.status, state.keys) {
+            (Some(status), _) => status.as_str(),
+            (None, false) => "enter run  ^e edit  ^d delete  ↑↓ move  ^u clear  tab keys  esc quit",
+            (None, true) => "enter run  e edit  d delete  j/k move  tab filter  q quit",
+        };
+        out.push_str(&fit(hints, cols));
+        out.push_str("\x1b[K\x1b[J");
+        self.tty.write_all(out.as_bytes())?;
+        self.tty.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Back to the main screen, with the cursor shown, leaving the screen as it was
+        let _ = self.tty.write_all(b"\x1b[?25h\x1b[?1049l");
+        // SAFETY: fd is open for the lifetime of `tty`
+        unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.original) };
+    }
+}
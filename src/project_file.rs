@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    command::{Command, Origin},
+    command_source::CommandSource,
+    document::{Document, Format},
+    error::{CxdError, Result},
+    trust,
+};
+
+/// Name of project files, discovered from the CWD or any of its parents
+pub const PROJECT_FILE_NAME: &str = ".cxd.toml";
+
+/// A read-only set of commands, checked into a project as a TOML document written by
+/// `--export --format toml`
+///
+/// Relative `dir` and `scope` values are relative to the directory containing the file, unless
+/// starting with a placeholder. An empty scope is the whole project, so project commands only
+/// resolve from within it.
+pub struct ProjectFile {
+    path: PathBuf,
+    fingerprint: String,
+    cmds: Vec<Command>,
+}
+
+impl ProjectFile {
+    /// Finds the nearest project file in `dir` or one of its parents
+    ///
+    /// # Returns
+    /// The parsed project file, or `None` if none found.
+    pub fn discover(dir: &Path) -> Result<Option<Self>> {
+        for dir in dir.ancestors() {
+            let path = dir.join(PROJECT_FILE_NAME);
+            if path.is_file() {
+                return Self::open(path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the project file at `path`, which must be absolute
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let contents = std::fs::read_to_string(&path)?;
+        let root = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let doc = Document::parse(&contents, Format::Toml).map_err(|e| match e {
+            CxdError::Document(reason) => CxdError::ProjectFile {
+                path: path.clone(),
+                reason,
+            },
+            e => e,
+        })?;
+        let cmds = doc
+            .commands
+            .into_iter()
+            .map(|entry| {
+                let mut cmd = Command::from(entry);
+                cmd.dir = resolve_dir(&root, &cmd.dir);
+                for step in &mut cmd.steps {
+                    step.dir = resolve_dir(&root, &step.dir);
+                }
+                cmd.scope = if cmd.scope.components().next().is_some() {
+                    root.join(&cmd.scope)
+                } else {
+                    root.clone()
+                };
+                cmd.origin = Origin::Project(path.clone());
+                cmd
+            })
+            .collect();
+        Ok(Self {
+            fingerprint: trust::fingerprint(contents.as_bytes()),
+            path,
+            cmds,
+        })
+    }
+
+    /// Absolute path of the project file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fingerprint of the file's contents, for checking whether it is trusted
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Resolves a relative directory against the project root, leaving empty directories and
+/// those starting with a placeholder, such as {git_root}
+fn resolve_dir(root: &Path, dir: &Path) -> PathBuf {
+    let templated = dir.to_str().is_some_and(|d| d.starts_with('{'));
+    if dir.is_relative() && !templated && dir.components().next().is_some() {
+        root.join(dir)
+    } else {
+        dir.to_path_buf()
+    }
+}
+
+impl CommandSource for ProjectFile {
+    /// Resolves the same as [`crate::command_store::CommandStore::resolve`], preferring the
+    /// nearest scope
+    fn resolve(&self, name: &str, dir: &Path) -> Result<Option<Command>> {
+        Ok(self
+            .cmds
+            .iter()
+            .filter(|c| c.name == name && dir.starts_with(&c.scope))
+            .max_by_key(|c| c.scope.components().count())
+            .cloned())
+    }
+
+    fn fetch_all(&self) -> Result<Vec<Command>> {
+        Ok(self.cmds.clone())
+    }
+}
@@ -0,0 +1,134 @@
+use std::borrow::Cow;
+
+/// Characters which never need quoting in a POSIX shell word
+fn is_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c)
+}
+
+/// Quotes `word` so a POSIX shell reads it back as a single word, unchanged
+///
+/// Words made only of safe characters are left as is. Words with control characters, such as
+/// newlines, use `$'...'` escapes to stay on one line, anything else is wrapped in single
+/// quotes, with each `'` written as `'\''`. [`split`] reads back any quoted word.
+pub fn quote(word: &str) -> Cow<'_, str> {
+    if !word.is_empty() && word.chars().all(is_safe) {
+        return Cow::Borrowed(word);
+    }
+    if !word.chars().any(char::is_control) {
+        return Cow::Owned(format!("'{}'", word.replace('\'', r"'\''")));
+    }
+    let mut ret = String::from("$'");
+    for c in word.chars() {
+        match c {
+            '\\' => ret.push_str(r"\\"),
+            '\'' => ret.push_str(r"\'"),
+            '\n' => ret.push_str(r"\n"),
+            '\t' => ret.push_str(r"\t"),
+            '\r' => ret.push_str(r"\r"),
+            c if c.is_ascii_control() => ret.push_str(&format!(r"\x{:02x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('\'');
+    Cow::Owned(ret)
+}
+
+/// Quotes each word, joined by spaces into a single line
+pub fn join<'a>(words: impl IntoIterator<Item = &'a str>) -> String {
+    words.into_iter().map(quote).collect::<Vec<_>>().join(" ")
+}
+
+/// Splits a line into words the same as a POSIX shell, without any expansions
+///
+/// Handles backslashes, single quotes, double quotes and `$'...'` escapes, so reverses
+/// [`join`]. `$` and backticks are kept as literal text.
+///
+/// # Returns
+/// The words, or the reason the line is not valid, such as an unterminated quote.
+pub fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    // Quotes may produce an empty word, so a word may be in progress while `word` is empty
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                continue;
+            }
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some(c) => word.push(c),
+                None => return Err("trailing backslash".into()),
+            },
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => word.push(c),
+                    None => return Err("unterminated single quote".into()),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('$' | '`' | '"' | '\\')) => word.push(c),
+                        Some('\n') => {}
+                        Some(c) => {
+                            word.push('\\');
+                            word.push(c);
+                        }
+                        None => return Err("unterminated double quote".into()),
+                    },
+                    Some(c) => word.push(c),
+                    None => return Err("unterminated double quote".into()),
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => word.push(unescape(&mut chars)?),
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated $' quote".into()),
+                    }
+                }
+            }
+            c => word.push(c),
+        }
+        in_word = true;
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Reads the escape sequence after a `\` within `$'...'`
+fn unescape(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<char, String> {
+    let c = chars.next().ok_or("unterminated $' quote")?;
+    Ok(match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'a' => '\x07',
+        'b' => '\x08',
+        'e' | 'E' => '\x1b',
+        'f' => '\x0c',
+        'v' => '\x0b',
+        'x' => {
+            let mut hex = String::new();
+            while hex.len() < 2 && chars.peek().is_some_and(char::is_ascii_hexdigit) {
+                hex.extend(chars.next());
+            }
+            u8::from_str_radix(&hex, 16).map_err(|_| r"expected hex digits after \x")? as char
+        }
+        c => c,
+    })
+}
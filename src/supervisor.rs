@@ -0,0 +1,100 @@
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::{Child, ExitStatus},
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use libc::c_int;
+
+/// Signals sent to `cxd` which are passed on to the child
+const FORWARDED: [c_int; 4] = [libc::SIGTERM, libc::SIGHUP, libc::SIGUSR1, libc::SIGUSR2];
+
+/// Signals a terminal sends to the whole foreground process group, so the child already
+/// receives them, and `cxd` must outlive it to record how it exited
+const IGNORED: [c_int; 2] = [libc::SIGINT, libc::SIGQUIT];
+
+/// PID of the supervised child, 0 until spawned
+static CHILD: AtomicI32 = AtomicI32::new(0);
+
+/// Last signal received before the child was spawned, forwarded once it is
+static PENDING: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward(sig: c_int) {
+    let pid = CHILD.load(Ordering::SeqCst);
+    if pid > 0 {
+        // SAFETY: kill is async-signal-safe
+        unsafe { libc::kill(pid, sig) };
+    } else {
+        PENDING.store(sig, Ordering::SeqCst);
+    }
+}
+
+// A handler, rather than SIG_IGN, as exec resets handlers but keeps ignored signals ignored
+extern "C" fn ignore(_: c_int) {}
+
+/// Installs `handler` for `sig`, restarting interrupted system calls
+fn set_handler(sig: c_int, handler: extern "C" fn(c_int)) -> std::io::Result<()> {
+    // SAFETY: sigaction is zeroable, and fully initialized before use
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(sig, &action, std::ptr::null_mut()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// A child process which `cxd` waits on instead of replacing itself with, forwarding signals
+/// sent to `cxd` until it exits
+pub struct Supervisor {
+    child: Child,
+}
+
+impl Supervisor {
+    /// Spawns `process`, with signals forwarded from the moment it starts
+    pub fn spawn(mut process: std::process::Command) -> std::io::Result<Self> {
+        for sig in IGNORED {
+            set_handler(sig, ignore)?;
+        }
+        for sig in FORWARDED {
+            set_handler(sig, forward)?;
+        }
+
+        let child = process.spawn()?;
+        CHILD.store(child.id() as i32, Ordering::SeqCst);
+        // Handlers interrupt this thread, so any signal before the store above is pending
+        let pending = PENDING.swap(0, Ordering::SeqCst);
+        if pending > 0 {
+            forward(pending);
+        }
+        Ok(Self { child })
+    }
+
+    /// Waits for the child to exit
+    pub fn wait(mut self) -> std::io::Result<ExitStatus> {
+        self.child.wait()
+    }
+}
+
+/// Exit code of a finished process, using the shell convention of 128 + N for signal N
+pub fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or(status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
+}
+
+/// Exits the same way as a finished child, re-raising the signal which killed it, if any
+pub fn exit_as(status: ExitStatus) -> ! {
+    if let Some(sig) = status.signal() {
+        // SAFETY: restoring the default action, so raising the signal terminates `cxd`
+        unsafe {
+            libc::signal(sig, libc::SIG_DFL);
+            libc::raise(sig);
+        }
+    }
+    std::process::exit(exit_code(status))
+}
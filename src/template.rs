@@ -0,0 +1,241 @@
+use std::{fmt::Display, path::Path};
+
+use crate::error::{CxdError, Result};
+
+/// A placeholder within a template, written as `{...}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placeholder {
+    /// `{N}`, the Nth runtime argument, starting at 1
+    Position(usize),
+    /// `{@}`, all runtime arguments
+    All,
+    /// `{cwd}`, the directory `cxd` was invoked from
+    Cwd,
+    /// `{name}`, the name of the executing command
+    Name,
+    /// `{git_root}`, the root of the git repository containing the invoking directory
+    GitRoot,
+    /// `{VAR}` or `{VAR:-default}`, an environment variable of the invoking environment
+    Env {
+        key: String,
+        default: Option<String>,
+    },
+}
+
+impl Placeholder {
+    /// Checks if the contents between a pair of braces are shaped like a placeholder, a single
+    /// word with an optional `:-default`, rather than e.g. `{}` or `{print $1}`
+    fn is_shaped(inner: &str) -> bool {
+        let key = inner.split_once(":-").map_or(inner, |(key, _)| key);
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+    }
+
+    /// Parses the contents between a pair of braces
+    ///
+    /// # Returns
+    /// The placeholder, or `None` if `inner` is not shaped like a placeholder and should be left
+    /// as literal text.
+    fn parse(inner: &str, template: &str) -> Result<Option<Self>> {
+        if !Self::is_shaped(inner) {
+            return Ok(None);
+        }
+        let (key, default) = match inner.split_once(":-") {
+            Some((key, default)) => (key, Some(default.to_owned())),
+            None => (inner, None),
+        };
+        let invalid = |reason: String| {
+            Err(CxdError::InvalidTemplate {
+                template: template.into(),
+                reason,
+            })
+        };
+
+        let placeholder = match key {
+            "@" => Placeholder::All,
+            "cwd" => Placeholder::Cwd,
+            "name" => Placeholder::Name,
+            "git_root" => Placeholder::GitRoot,
+            k if k.chars().all(|c| c.is_ascii_digit()) => match k.parse() {
+                Ok(0) | Err(_) => return invalid("positions start at {1}".into()),
+                Ok(n) => Placeholder::Position(n),
+            },
+            k if !k.starts_with(|c: char| c.is_ascii_digit())
+                && k.chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') =>
+            {
+                return Ok(Some(Placeholder::Env {
+                    key: k.into(),
+                    default,
+                }));
+            }
+            // Likely a typo, or meant for the command itself, e.g. `awk '{print}'`
+            _ => {
+                return invalid(format!(
+                    "unknown placeholder {{{inner}}}, write \\{{{inner}}} to pass it as is"
+                ))
+            }
+        };
+        if default.is_some() {
+            return invalid(format!(
+                "only environment variables may have a default, found {{{inner}}}"
+            ));
+        }
+        Ok(Some(placeholder))
+    }
+
+    /// Expands the placeholder to its value
+    fn expand(&self, ctx: &Context) -> Result<String> {
+        let unresolved = |reason: String| CxdError::UnresolvedPlaceholder {
+            name: ctx.name.into(),
+            placeholder: self.to_string(),
+            reason,
+        };
+        match self {
+            Placeholder::Position(n) => ctx.args.get(n - 1).cloned().ok_or_else(|| {
+                unresolved(format!(
+                    "requires at least {n} arguments, found {}",
+                    ctx.args.len()
+                ))
+            }),
+            Placeholder::All => Ok(ctx.args.join(" ")),
+            Placeholder::Cwd => Ok(ctx.cwd.to_str().unwrap_or_default().into()),
+            Placeholder::Name => Ok(ctx.name.into()),
+            Placeholder::GitRoot => ctx
+                .cwd
+                .ancestors()
+                .find(|p| p.join(".git").exists())
+                .map(|p| p.to_str().unwrap_or_default().into())
+                .ok_or_else(|| unresolved("not within a git repository".into())),
+            Placeholder::Env { key, default } => match (std::env::var(key), default) {
+                // Matching shell semantics, `:-` also substitutes empty variables
+                (Ok(v), Some(d)) if v.is_empty() => Ok(d.clone()),
+                (Ok(v), _) => Ok(v),
+                (Err(_), Some(d)) => Ok(d.clone()),
+                (Err(_), None) => Err(unresolved("environment variable is not set".into())),
+            },
+        }
+    }
+}
+
+impl Display for Placeholder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Placeholder::Position(n) => write!(f, "{{{n}}}"),
+            Placeholder::All => write!(f, "{{@}}"),
+            Placeholder::Cwd => write!(f, "{{cwd}}"),
+            Placeholder::Name => write!(f, "{{name}}"),
+            Placeholder::GitRoot => write!(f, "{{git_root}}"),
+            Placeholder::Env { key, default: None } => write!(f, "{{{key}}}"),
+            Placeholder::Env {
+                key,
+                default: Some(d),
+            } => write!(f, "{{{key}:-{d}}}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// Values placeholders are expanded from at execution time
+pub struct Context<'a> {
+    /// Name of the executing command
+    pub name: &'a str,
+    /// Runtime arguments passed after the command name
+    pub args: &'a [String],
+    /// Directory `cxd` was invoked from
+    pub cwd: &'a Path,
+}
+
+/// A string containing placeholders to expand at execution time
+///
+/// Braces which aren't shaped like a placeholder are left as is, so `{}`, `${VAR}`, `{print $1}`
+/// and `{{.Names}}` need no escaping. Otherwise, `\{...}` passes `{...}` as is.
+#[derive(Debug)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses a template, failing on unknown or invalid placeholders such as `{nope}` or `{0}`
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(pos) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            // Go templates, such as `{{.Names}}` or `{{end}}`, are never placeholders
+            if rest.starts_with("{{") {
+                literal.push_str("{{");
+                rest = &rest[2..];
+                continue;
+            }
+            // Shell style `${VAR}` is left for the shell to expand
+            let shell_var = literal.ends_with('$');
+            if let Some(end) = rest
+                .find('}')
+                .filter(|_| rest.starts_with('{') && !shell_var)
+            {
+                let inner = &rest[1..end];
+                if literal.ends_with('\\') && Placeholder::is_shaped(inner) {
+                    literal.pop();
+                    literal.push_str(&rest[..=end]);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+                if let Some(placeholder) = Placeholder::parse(inner, template)? {
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+            literal.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Iterates over all placeholders within the template
+    pub fn placeholders(&self) -> impl Iterator<Item = &Placeholder> {
+        self.segments.iter().filter_map(|s| match s {
+            Segment::Placeholder(p) => Some(p),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Expands all placeholders into a single string
+    pub fn expand(&self, ctx: &Context) -> Result<String> {
+        let mut ret = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(l) => ret.push_str(l),
+                Segment::Placeholder(p) => ret.push_str(&p.expand(ctx)?),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Expands all placeholders into arguments. A template of only `{@}` expands to each runtime
+    /// argument separately, otherwise this is a single argument.
+    pub fn expand_args(&self, ctx: &Context) -> Result<Vec<String>> {
+        if let [Segment::Placeholder(Placeholder::All)] = self.segments.as_slice() {
+            Ok(ctx.args.to_vec())
+        } else {
+            Ok(vec![self.expand(ctx)?])
+        }
+    }
+}
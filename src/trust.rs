@@ -0,0 +1,227 @@
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{BufRead, IsTerminal, Write},
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    command_source::CommandSource,
+    command_store::CommandStore,
+    document::{Document, Format},
+    error::{CxdError, Result},
+};
+
+/// Fingerprints contents as a hex encoded SHA-256 digest
+pub fn fingerprint(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .fold(String::new(), |mut ret, b| {
+            let _ = write!(ret, "{b:02x}");
+            ret
+        })
+}
+
+/// Fingerprints the commands of a cache, rather than its file, as SQLite may rewrite the file
+/// without changing its contents
+pub fn cache_fingerprint(c: &CommandStore) -> Result<String> {
+    let doc = Document::new(c.fetch_all()?).serialize(Format::Json)?;
+    Ok(fingerprint(doc.as_bytes()))
+}
+
+/// Files the user has approved running commands from, along with the fingerprint of their
+/// contents when approved
+///
+/// Stored one file per line, in the same format as `sha256sum`. Changes are made under a lock on
+/// `<path>.lock`, merging with any made by other `cxd` processes since the database was read.
+pub struct TrustStore {
+    path: PathBuf,
+    entries: Vec<(String, PathBuf)>,
+    // Held from [`Self::lock`] until [`Self::unlock`]
+    lock: Option<File>,
+}
+
+impl TrustStore {
+    /// Reads the trust database at `path`, which is empty if not present
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let mut ret = Self {
+            path,
+            entries: vec![],
+            lock: None,
+        };
+        ret.reload()?;
+        Ok(ret)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => Err(e)?,
+        };
+        self.entries = contents
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, file)| (hash.into(), file.into()))
+            .collect();
+        Ok(())
+    }
+
+    /// Locks the database against changes by other `cxd` processes, then re-reads it
+    ///
+    /// Held until [`Self::unlock`] or exit, so a cache can be written and its new fingerprint
+    /// trusted without another process trusting an older fingerprint in between.
+    pub fn lock(&mut self) -> Result<()> {
+        if self.lock.is_some() {
+            return Ok(());
+        }
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(path)?;
+        // SAFETY: The file descriptor is open for the duration of the call
+        while unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(e.into());
+            }
+        }
+        self.lock = Some(file);
+        self.reload()
+    }
+
+    /// Releases the lock taken by [`Self::lock`]
+    pub fn unlock(&mut self) {
+        // Closing the file releases the lock
+        self.lock = None;
+    }
+
+    /// Checks if writes to the cache `c` at `file` should keep it trusted, as it is either
+    /// empty or trusted with its current contents
+    pub fn keeps_trust(&self, file: &Path, c: &CommandStore) -> Result<bool> {
+        Ok(c.fetch_all()?.is_empty() || self.is_trusted(file, &cache_fingerprint(c)?))
+    }
+
+    /// Checks if `file` was trusted with contents matching `fingerprint`
+    pub fn is_trusted(&self, file: &Path, fingerprint: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|(hash, f)| f == file && hash == fingerprint)
+    }
+
+    /// Trusts `file` with contents matching `fingerprint`, replacing any previous fingerprint
+    pub fn trust(&mut self, file: &Path, fingerprint: &str) -> Result<()> {
+        self.update(|entries| {
+            if entries.iter().any(|(h, f)| f == file && h == fingerprint) {
+                return false;
+            }
+            entries.retain(|(_, f)| f != file);
+            entries.push((fingerprint.into(), file.into()));
+            true
+        })?;
+        Ok(())
+    }
+
+    /// Removes any trust of `file`
+    ///
+    /// # Returns
+    /// `false` if `file` was not trusted.
+    pub fn untrust(&mut self, file: &Path) -> Result<bool> {
+        self.update(|entries| {
+            let len = entries.len();
+            entries.retain(|(_, f)| f != file);
+            entries.len() != len
+        })
+    }
+
+    /// Applies `change` to the database as currently stored, saving it if `change` returns true
+    ///
+    /// Locks the database for the duration, unless already locked.
+    fn update(&mut self, change: impl FnOnce(&mut Vec<(String, PathBuf)>) -> bool) -> Result<bool> {
+        let locked = self.lock.is_some();
+        self.lock()?;
+        let changed = change(&mut self.entries);
+        let ret = if changed { self.save() } else { Ok(()) };
+        if !locked {
+            self.unlock();
+        }
+        ret.map(|_| changed)
+    }
+
+    /// Fails unless `file` is trusted, first asking the user if interactive
+    ///
+    /// When asking, all commands of `source` are shown, so they can be reviewed before any
+    /// of them are executed.
+    ///
+    /// # Args
+    /// * `file` - Absolute path of the cache or project file
+    /// * `fingerprint` - Fingerprint of the current contents of `file`
+    /// * `source` - Commands contained in `file`
+    pub fn ensure(
+        &mut self,
+        file: &Path,
+        fingerprint: &str,
+        source: &dyn CommandSource,
+    ) -> Result<()> {
+        if self.is_trusted(file, fingerprint) {
+            return Ok(());
+        }
+        let changed = self.entries.iter().any(|(_, f)| f == file);
+        if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+            return Err(CxdError::Untrusted {
+                path: file.into(),
+                changed,
+            });
+        }
+
+        let mut stderr = std::io::stderr().lock();
+        if changed {
+            writeln!(
+                stderr,
+                "{} has changed since it was trusted.",
+                file.display()
+            )?;
+        } else {
+            writeln!(stderr, "{} is not trusted yet.", file.display())?;
+        }
+        writeln!(stderr, "It contains the following commands:\n")?;
+        for cmd in source.fetch_all()? {
+            writeln!(stderr, "{}\n", cmd)?;
+        }
+        write!(stderr, "Trust it and allow running these commands? [yn]: ")?;
+        stderr.flush()?;
+        let response = std::io::stdin()
+            .lock()
+            .lines()
+            .next()
+            .ok_or(CxdError::Stdin)??;
+        if response.to_lowercase() != "y" {
+            return Err(CxdError::Untrusted {
+                path: file.into(),
+                changed,
+            });
+        }
+        self.trust(file, fingerprint)
+    }
+
+    /// Writes the database, replacing the file at once so concurrent readers never see it
+    /// partially written
+    fn save(&self) -> Result<()> {
+        let mut contents = String::new();
+        for (hash, file) in &self.entries {
+            let _ = writeln!(contents, "{hash}  {}", file.display());
+        }
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        std::fs::write(&tmp, contents)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
@@ -1,2 +1,3 @@
 mod ops;
+mod store;
 pub mod util;
@@ -270,3 +270,42 @@ fn add_cwd() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn add_cwd_flag() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let path = std::fs::canonicalize(dir.as_ref())?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.current_dir(&path)
+        .arg("--add")
+        .arg("--cwd")
+        .arg("test")
+        .arg("pwd")
+        .assert()
+        .success();
+
+    // Runs from the saved directory, wherever invoked from
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir("/")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", path.display()));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--cwd")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("other")
+        .arg("pwd")
+        .assert()
+        .failure()
+        .stderr(contains("--dir"));
+
+    Ok(())
+}
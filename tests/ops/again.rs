@@ -0,0 +1,211 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn again() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("test").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(dir.as_ref())
+        .arg("--record")
+        .arg("test")
+        .arg("one")
+        .arg("two")
+        .assert()
+        .success()
+        .stdout("one two\n");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--again")
+        .assert()
+        .success()
+        .stdout("one two\n")
+        .stderr("cxd test one two\n");
+
+    Ok(())
+}
+
+#[test]
+fn again_failed() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("fail")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo failed; exit 3")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("pass")
+        .arg("echo")
+        .arg("passed")
+        .assert()
+        .success();
+
+    for name in ["fail", "pass"] {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.current_dir(dir.as_ref())
+            .arg("--record")
+            .arg(name)
+            .assert();
+    }
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--again")
+        .arg("--failed")
+        .assert()
+        .code(3)
+        .stdout("failed\n");
+
+    // Rerun supervised, so recorded again
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--history")
+        .arg("--failed")
+        .assert()
+        .success()
+        .stdout(contains("[1]: fail").and(contains("[3]: fail")));
+
+    Ok(())
+}
+
+#[test]
+fn again_scoped() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let first = dir.as_ref().join("first");
+    let second = dir.as_ref().join("second");
+    std::fs::create_dir(&first)?;
+    std::fs::create_dir(&second)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("where").arg("pwd").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(&first)
+        .arg("--record")
+        .arg("where")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(&second)
+        .arg("--again")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("no previous run found from").and(contains("--anywhere")));
+
+    // Runs from the original directory
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(&second)
+        .arg("--again")
+        .arg("--anywhere")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", first.to_str().unwrap()));
+
+    Ok(())
+}
+
+#[test]
+fn again_same_command() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(dir.as_ref())
+        .arg("--record")
+        .arg("test")
+        .arg("one")
+        .assert()
+        .success()
+        .stdout("global one\n");
+
+    // A command added since which shadows the name isn't the one that ran
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--add")
+        .arg("--local")
+        .arg("test")
+        .arg("echo")
+        .arg("local")
+        .assert()
+        .success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(dir.as_ref())
+        .arg("--again")
+        .assert()
+        .success()
+        .stdout("global one\n");
+
+    // Nor is it lost once renamed, from outside the scope of the shadowing command
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--rename")
+        .arg("test")
+        .arg("renamed")
+        .assert()
+        .success()
+        .stdout(contains("renamed"));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.current_dir(dir.as_ref())
+        .arg("--again")
+        .assert()
+        .success()
+        .stdout("global one\n");
+
+    Ok(())
+}
+
+#[test]
+fn again_no_args() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--again")
+        .arg("extra")
+        .assert()
+        .failure()
+        .stderr(contains("again requires 0 arguments"));
+
+    Ok(())
+}
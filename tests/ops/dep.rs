@@ -0,0 +1,424 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn run_once_in_order() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    // Extra args only go to the command itself
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("deploy")
+        .arg("now")
+        .assert()
+        .success()
+        .stdout("build\ntest\ndeploy now\n");
+
+    // Only the command itself is recorded
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history")
+        .assert()
+        .success()
+        .stdout(contains("]: deploy").and(contains("]: build").not()));
+
+    Ok(())
+}
+
+#[test]
+fn failed_dep() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--edit")
+        .arg("build")
+        .arg("--set-cmd")
+        .arg("false")
+        .assert()
+        .success();
+
+    // Keeping going only applies to the command's own steps
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--keep-going")
+        .arg("deploy")
+        .assert()
+        .code(1)
+        .stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn tree() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--deps").arg("deploy").assert().success().stdout(
+        "deploy [3]\n\
+         ├── build [1]\n\
+         └── test [2]\n    \
+             └── build [1] (*)\n",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cycle() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--edit")
+        .arg("build")
+        .arg("--add-dep")
+        .arg("deploy")
+        .assert()
+        .success();
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("deploy")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("dependency cycle: deploy -> build -> deploy"));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--edit")
+        .arg("build")
+        .arg("--add-dep")
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(contains("dependency cycle: build -> build"));
+
+    Ok(())
+}
+
+#[test]
+fn missing_dep() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--remove").arg("build").assert().success();
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("deploy").assert().failure().stderr(contains(
+        "command \"deploy\" depends on \"build\", which was not found",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn edit_deps() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--edit")
+        .arg("deploy")
+        .arg("--remove-dep")
+        .arg("build")
+        .assert()
+        .success()
+        .stdout(contains("deps: test\n"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--edit")
+        .arg("deploy")
+        .arg("--add-dep")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("duplicate dependency"));
+
+    // Still ordered after the dependencies of test
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("deploy")
+        .assert()
+        .success()
+        .stdout("build\ntest\ndeploy\n");
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dep")
+        .arg("test")
+        .arg("deploy")
+        .arg("echo")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd4.arg("--export").arg("deploy").output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout.clone())?.contains("\"deps\""));
+
+    let other = TempCacheDir::new()?;
+    let mut cmd5 = assert_cmd::Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", other.as_ref());
+    cmd5.arg("--import")
+        .arg("-")
+        .write_stdin(output.stdout)
+        .assert()
+        .success();
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", other.as_ref());
+    cmd6.arg("--list")
+        .assert()
+        .success()
+        .stdout(contains("deps: build test"));
+
+    Ok(())
+}
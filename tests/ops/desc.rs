@@ -0,0 +1,202 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn grep() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--desc")
+        .arg("Deploys to staging\n\n{1} is the git ref")
+        .arg("deploy")
+        .arg("echo")
+        .arg("{1}")
+        .assert()
+        .success()
+        .stdout(contains(
+            "]: deploy\n  desc: Deploys to staging\n        \n        {1} is the git ref\n",
+        ));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--list")
+        .arg("--grep")
+        .arg("GIT REF")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("deploy\n");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--list")
+        .arg("--format")
+        .arg("tsv")
+        .assert()
+        .success()
+        .stdout(contains("\tDeploys to staging\\n\\n{1} is the git ref\n"));
+
+    Ok(())
+}
+
+#[test]
+fn editor() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--desc")
+        .arg("Deploys to staging\n\n{1} is the git ref")
+        .arg("deploy")
+        .arg("echo")
+        .arg("{1}")
+        .assert()
+        .success()
+        .stdout(contains(
+            "]: deploy\n  desc: Deploys to staging\n        \n        {1} is the git ref\n",
+        ));
+
+    // Lines are written as is, and read back unchanged
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env("VISUAL", "true")
+        .arg("--edit")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("No changes"));
+
+    // Appends an unquoted line after the existing description
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.env(
+        "VISUAL",
+        "sed -i -e '/^desc: {1}/a desc: Needs VPN access, slow'",
+    )
+    .arg("--edit")
+    .arg("deploy")
+    .assert()
+    .success()
+    .stdout(contains(
+        "        {1} is the git ref\n        Needs VPN access, slow\n",
+    ));
+
+    // Removing every desc line removes the description
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.env("VISUAL", "sed -i -e '/^desc:/d'")
+        .arg("--edit")
+        .arg("deploy")
+        .assert()
+        .success()
+        .stdout(contains("desc:").not());
+
+    Ok(())
+}
+
+#[test]
+fn set_desc() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--desc")
+        .arg("Deploys to staging\n\n{1} is the git ref")
+        .arg("deploy")
+        .arg("echo")
+        .arg("{1}")
+        .assert()
+        .success()
+        .stdout(contains(
+            "]: deploy\n  desc: Deploys to staging\n        \n        {1} is the git ref\n",
+        ));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("deploy")
+        .arg("--set-desc")
+        .arg("Deploys anywhere")
+        .assert()
+        .success()
+        .stdout(contains("  desc: Deploys anywhere\n  scope: global\n"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--edit")
+        .arg("deploy")
+        .arg("--set-desc")
+        .arg("")
+        .assert()
+        .success()
+        .stdout(contains("desc:").not());
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--desc")
+        .arg("Deploys to staging\n\n{1} is the git ref")
+        .arg("deploy")
+        .arg("echo")
+        .arg("{1}")
+        .assert()
+        .success()
+        .stdout(contains(
+            "]: deploy\n  desc: Deploys to staging\n        \n        {1} is the git ref\n",
+        ));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd2.arg("--export").arg("deploy").output()?;
+    assert!(output.status.success());
+    let document = String::from_utf8(output.stdout)?;
+    assert!(document.contains("\"description\": \"Deploys to staging\\n\\n{1} is the git ref\""));
+
+    let dir2 = TempCacheDir::new()?;
+    let mut cmd3 = assert_cmd::Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd3.arg("--import")
+        .arg("-")
+        .write_stdin(document)
+        .assert()
+        .success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd4.arg("--list").assert().success().stdout(contains(
+        "  desc: Deploys to staging\n        \n        {1} is the git ref\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn requires_add() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .arg("--desc")
+        .arg("text")
+        .assert()
+        .failure()
+        .stderr(contains("option --desc requires operation -a, --add"));
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn dry_run() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("built")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dir")
+        .arg(dir.as_ref())
+        .arg("--env")
+        .arg("CXD_TEST=a b")
+        .arg("test")
+        .arg("echo")
+        .arg("it's {1}")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("./missing")
+        .assert()
+        .success();
+
+    let cwd = std::env::current_dir()?;
+    let dir_str = dir.as_ref().to_str().unwrap();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref())
+        .env("PATH", "/nonexistent:/bin:/usr/bin");
+    cmd4.arg("--dry-run")
+        .arg("test")
+        .arg("x")
+        .assert()
+        .success()
+        .stdout(
+            contains(format!("build\n  dir: {}\n", cwd.display()))
+                .and(contains("  argv: echo built\n\ntest\n"))
+                .and(contains(format!("  dir: {dir_str}\n  env: CXD_TEST=a b\n")))
+                .and(contains("  program: /bin/echo\n"))
+                .and(contains("  argv: echo 'it'\\''s x'\n"))
+                .and(contains("test step 1\n"))
+                .and(contains("  program: ./missing (not found)\n")),
+        );
+
+    // Nothing ran, so nothing was recorded
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history").assert().success().stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn print_shell() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("built")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dir")
+        .arg(dir.as_ref())
+        .arg("--env")
+        .arg("CXD_TEST=a b")
+        .arg("test")
+        .arg("echo")
+        .arg("it's {1}")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("./missing")
+        .assert()
+        .success();
+
+    let dir_str = dir.as_ref().to_str().unwrap();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--print-shell")
+        .arg("test")
+        .arg("x")
+        .assert()
+        .success()
+        .stdout(format!(
+            "echo built &&\n\
+             (cd {dir_str} && CXD_TEST='a b' echo 'it'\\''s x') &&\n\
+             (cd {dir_str} && CXD_TEST='a b' ./missing)\n"
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn print_shell_runs() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=$HOME")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("printf '%s|' \"$CXD_TEST\" \"$@\"")
+        .arg("-")
+        .arg("{1}")
+        .arg("*")
+        .assert()
+        .success();
+
+    // Pasting the line runs the same as the command
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    let line = cmd2
+        .arg("--print-shell")
+        .arg("test")
+        .arg("a\"'b")
+        .output()?;
+    let mut cmd3 = Command::new("sh");
+    cmd3.arg("-c").arg(String::from_utf8(line.stdout)?);
+    cmd3.assert().success().stdout("$HOME|a\"'b|*|");
+
+    Ok(())
+}
+
+#[test]
+fn untrusted() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("built")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--dir")
+        .arg(dir.as_ref())
+        .arg("--env")
+        .arg("CXD_TEST=a b")
+        .arg("test")
+        .arg("echo")
+        .arg("it's {1}")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("./missing")
+        .assert()
+        .success();
+
+    std::fs::remove_file(dir.as_ref().join("cxd.trust"))?;
+
+    // Reviewing an untrusted command is allowed
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--dry-run")
+        .arg("build")
+        .assert()
+        .success()
+        .stdout(contains("argv: echo built"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("build")
+        .assert()
+        .failure()
+        .stderr(contains("is not trusted"));
+
+    Ok(())
+}
+
+#[test]
+fn incompatible() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .arg("--print-shell")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "option --print-shell requires operation <NAME>, --again or --run-tag",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn set_args() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("test")
+        .arg("--set-args")
+        .arg("--help")
+        .arg("there")
+        .assert()
+        .success()
+        .stdout(contains("Updated [1]: test"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("--help there"));
+
+    Ok(())
+}
+
+#[test]
+fn set_cmd_dir() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("--set-cmd")
+        .arg("pwd")
+        .arg("--set-dir")
+        .arg(dir.as_ref())
+        .arg("test")
+        .arg("--set-args")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains(dir.as_ref().to_str().unwrap()));
+
+    Ok(())
+}
+
+#[test]
+fn envs() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("test")
+        .arg("--unset-env")
+        .arg("CXD_TEST1")
+        .arg("--add-env")
+        .arg("CXD_TEST2=two")
+        .arg("--set-cmd")
+        .arg("sh")
+        .arg("--set-args")
+        .arg("-c")
+        .arg("echo ${CXD_TEST1:-unset} $CXD_TEST2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("unset two"));
+
+    Ok(())
+}
+
+#[test]
+fn editor() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env_remove("VISUAL")
+        .env(
+            "EDITOR",
+            "sed -i -e 's/^arg: hi$/arg: bye/' -e '$a arg: there'",
+        )
+        .arg("--edit")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("Updated"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("bye there"));
+
+    Ok(())
+}
+
+#[test]
+fn editor_unchanged() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env("VISUAL", "true")
+        .arg("--edit")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("No changes").and(contains("Updated").not()));
+
+    Ok(())
+}
+
+#[test]
+fn editor_invalid() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env("VISUAL", "sed -i -e '$a bogus'")
+        .arg("--edit")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("failed to parse edited command"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test").assert().success().stdout(contains("hi"));
+
+    Ok(())
+}
+
+#[test]
+fn editor_temp_file() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let tmp = dir.as_ref().join("tmp");
+    std::fs::create_dir(&tmp)?;
+    let log = dir.as_ref().join("editor.log");
+
+    // Only the user may read the file, which is removed whether or not the editor succeeds
+    for (visual, success) in [("stat -c %a", true), ("false", false)] {
+        let mut cmd2 = Command::cargo_bin("cxd")?;
+        cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+        let assert = cmd2
+            .env("TMPDIR", &tmp)
+            .env("VISUAL", format!("{visual} >>{}", log.display()))
+            .arg("--edit")
+            .arg("test")
+            .assert();
+        if success {
+            assert.success();
+        } else {
+            assert.failure();
+        }
+        assert_eq!(std::fs::read_dir(&tmp)?.count(), 0);
+    }
+    assert_eq!(std::fs::read_to_string(&log)?, "600\n");
+
+    Ok(())
+}
+
+#[test]
+fn not_found() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--edit")
+        .arg("test")
+        .arg("--set-cmd")
+        .arg("true")
+        .assert()
+        .failure()
+        .stderr(contains("command not found"));
+
+    Ok(())
+}
+
+#[test]
+fn arg_order() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("test")
+        .arg("--set-args")
+        .arg("c")
+        .arg("b")
+        .arg("a")
+        .assert()
+        .success();
+
+    // Rewritten args keep their order, regardless of the rowids they're given
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute("UPDATE cxd_arg SET id = id * -1", ())?;
+    drop(c);
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("c b a"));
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn extra_args() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("saved")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("extra")
+        .arg("args")
+        .assert()
+        .success()
+        .stdout(contains("saved extra args"));
+
+    Ok(())
+}
+
+#[test]
+fn extra_args_separator() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("saved")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("--")
+        .arg("--")
+        .arg("extra")
+        .assert()
+        .success()
+        .stdout(contains("saved -- extra"));
+
+    Ok(())
+}
+
+#[test]
+fn extra_args_not_options() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add").arg("test").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("--help")
+        .arg("--list")
+        .arg("-f")
+        .assert()
+        .success()
+        .stdout(contains("--help --list -f").and(contains("Usage").not()));
+
+    Ok(())
+}
+
+#[test]
+fn no_name() -> anyhow::Result<()> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd.assert()
+        .failure()
+        .stderr(contains("exec requires 1 arguments, found 0"));
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn json() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST2=two")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test1")
+        .arg("echo")
+        .arg("hi")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test2")
+        .arg("true")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd3.arg("--export").output()?;
+    assert!(output.status.success());
+
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        doc,
+        serde_json::json!({
+            "version": 1,
+            "commands": [
+                {
+                    "name": "test1",
+                    "command": "echo",
+                    "args": ["hi", "{1}"],
+                    "envs": {"CXD_TEST2": "two", "CXD_TEST1": "one"},
+                },
+                {
+                    "name": "test2",
+                    "command": "true",
+                },
+            ],
+        })
+    );
+    // Envs keep their order
+    let text = String::from_utf8(output.stdout)?;
+    assert!(text.find("CXD_TEST2") < text.find("CXD_TEST1"));
+
+    Ok(())
+}
+
+#[test]
+fn toml() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST2=two")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test1")
+        .arg("echo")
+        .arg("hi")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test2")
+        .arg("true")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--export")
+        .arg("--format")
+        .arg("toml")
+        .assert()
+        .success()
+        .stdout(
+            contains("version = 1")
+                .and(contains("[[commands]]\nname = \"test1\""))
+                .and(contains("args = [\"hi\", \"{1}\"]"))
+                .and(contains("CXD_TEST2 = \"two\"\nCXD_TEST1 = \"one\"")),
+        );
+
+    Ok(())
+}
+
+#[test]
+fn by_name() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST2=two")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test1")
+        .arg("echo")
+        .arg("hi")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test2")
+        .arg("true")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--export")
+        .arg("test2")
+        .assert()
+        .success()
+        .stdout(contains("test2").and(contains("test1").not()));
+
+    Ok(())
+}
+
+#[test]
+fn not_found() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST2=two")
+        .arg("--env")
+        .arg("CXD_TEST1=one")
+        .arg("test1")
+        .arg("echo")
+        .arg("hi")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test2")
+        .arg("true")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--export")
+        .arg("test1")
+        .arg("test3")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("command not found: \"test3\""));
+
+    Ok(())
+}
+
+#[test]
+fn bad_format() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--export")
+        .arg("--format")
+        .arg("yaml")
+        .assert()
+        .failure()
+        .stderr(contains("expected one of json, toml"));
+
+    Ok(())
+}
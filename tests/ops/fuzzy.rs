@@ -0,0 +1,146 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn suggestions() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for name in ["build", "bench", "test"] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add")
+            .arg(name)
+            .arg("echo")
+            .arg(name)
+            .assert()
+            .success();
+    }
+
+    // A typo
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("biuld").assert().failure().stderr(contains(
+        r#"command not found: "biuld", did you mean "build"?"#,
+    ));
+
+    // A subsequence of several names, tighter matches first
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--edit")
+        .arg("b")
+        .assert()
+        .failure()
+        .stderr(contains(r#"did you mean "bench" or "build"?"#));
+
+    // Nothing similar
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--deps")
+        .arg("xyz")
+        .assert()
+        .failure()
+        .stderr(contains("command not found: \"xyz\"\n"));
+
+    Ok(())
+}
+
+#[test]
+fn scoped_elsewhere() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--scope")
+        .arg(dir.as_ref())
+        .arg("build")
+        .arg("echo")
+        .assert()
+        .success();
+
+    // Never suggests a command which would not resolve
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir("/")
+        .arg("biuld")
+        .assert()
+        .failure()
+        .stderr(contains("did you mean").not());
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_unique() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for name in ["build", "bench", "test"] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add")
+            .arg(name)
+            .arg("echo")
+            .arg(name)
+            .assert()
+            .success();
+    }
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--fuzzy")
+        .arg("bld")
+        .arg("extra")
+        .assert()
+        .success()
+        .stdout("build extra\n")
+        .stderr("cxd build extra\n");
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_ambiguous() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for name in ["build", "bench", "test"] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add")
+            .arg(name)
+            .arg("echo")
+            .arg(name)
+            .assert()
+            .success();
+    }
+
+    // Without a terminal to ask from, nothing runs
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--fuzzy")
+        .arg("b")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains(r#"did you mean "bench" or "build"?"#));
+
+    Ok(())
+}
+
+#[test]
+fn fuzzy_requires_name() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .arg("--fuzzy")
+        .assert()
+        .failure()
+        .stderr(contains("option --fuzzy requires operation <NAME>"));
+
+    Ok(())
+}
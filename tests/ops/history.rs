@@ -0,0 +1,247 @@
+use std::{os::unix::process::ExitStatusExt, process::Command, time::Duration};
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn record() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("pass").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("fail")
+        .arg("sh")
+        .arg("-c")
+        .arg("exit 3")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--record")
+        .arg("pass")
+        .arg("extra")
+        .arg("args")
+        .assert()
+        .success()
+        .stdout("extra args\n");
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--record").arg("fail").assert().code(3);
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history").assert().success().stdout(
+        contains("[1]: pass")
+            .and(contains(format!("cwd: {}", dir.as_ref().to_str().unwrap())))
+            .and(contains("args: extra args"))
+            .and(contains("exit: 0"))
+            .and(contains("[2]: fail"))
+            .and(contains("exit: 3")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn record_env() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("pass").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("fail")
+        .arg("sh")
+        .arg("-c")
+        .arg("exit 3")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("pass").assert().success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.env("CXD_RECORD", "1").arg("fail").assert().code(3);
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    // Unsupervised runs replace cxd, so are never recorded
+    cmd5.arg("--history").assert().success().stdout(
+        contains("[1]: fail\n")
+            .and(contains("exit: 3"))
+            .and(contains("pass").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn filters() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("pass").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("fail")
+        .arg("sh")
+        .arg("-c")
+        .arg("exit 3")
+        .assert()
+        .success();
+
+    for name in ["pass", "fail", "pass"] {
+        let mut cmd3 = Command::cargo_bin("cxd")?;
+        cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd3.arg("--record").arg(name).assert();
+    }
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--history").arg("pass").assert().success().stdout(
+        contains("[1]: pass")
+            .and(contains("[3]: pass"))
+            .and(contains("fail").not()),
+    );
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history")
+        .arg("--failed")
+        .assert()
+        .success()
+        .stdout(contains("[2]: fail").and(contains("pass").not()));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--history")
+        .arg("--since")
+        .arg("1h")
+        .arg("--until")
+        .arg("2099-01-01 00:00")
+        .assert()
+        .success()
+        .stdout(contains("[1]: pass").and(contains("[3]: pass")));
+
+    let mut cmd7 = Command::cargo_bin("cxd")?;
+    cmd7.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd7.arg("--history")
+        .arg("--until")
+        .arg("86400")
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd8 = Command::cargo_bin("cxd")?;
+    cmd8.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd8.arg("--history")
+        .arg("--since")
+        .arg("yesterday-ish")
+        .assert()
+        .failure()
+        .stderr(contains("failed to parse argument \"yesterday-ish\""));
+
+    Ok(())
+}
+
+#[test]
+fn removed_command() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("pass").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("fail")
+        .arg("sh")
+        .arg("-c")
+        .arg("exit 3")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--record").arg("pass").assert().success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--remove").arg("pass").assert().success();
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history")
+        .arg("pass")
+        .assert()
+        .success()
+        .stdout(contains("[1]: pass"));
+
+    Ok(())
+}
+
+#[test]
+fn forward_signal() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("slow")
+        .arg("sleep")
+        .arg("10")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    let mut child = cmd2.arg("--record").arg("slow").spawn()?;
+    std::thread::sleep(Duration::from_millis(500));
+    // SAFETY: signalling a child we spawned
+    unsafe { libc::kill(child.id() as i32, libc::SIGTERM) };
+    let status = child.wait()?;
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--history")
+        .assert()
+        .success()
+        .stdout(contains("exit: 143"));
+
+    Ok(())
+}
+
+#[test]
+fn record_requires_exec() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--record")
+        .arg("--list")
+        .assert()
+        .failure()
+        .stderr(contains("option --record requires"));
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn exec() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("greet")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--scope")
+        .arg(dir.as_ref())
+        .arg("greet")
+        .arg("echo")
+        .arg("scoped")
+        .assert()
+        .success();
+
+    // Found whatever the scope, with extra arguments passed through
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--id")
+        .arg("1")
+        .arg("--")
+        .arg("extra")
+        .assert()
+        .success()
+        .stdout("global extra\n");
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("-i")
+        .arg("2")
+        .assert()
+        .success()
+        .stdout("scoped\n");
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--id")
+        .arg("3")
+        .assert()
+        .failure()
+        .stderr(contains("command not found: ID 3"));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--id")
+        .arg("greet")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "failed to parse argument \"greet\": not an integer",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn show() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("greet")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--scope")
+        .arg(dir.as_ref())
+        .arg("greet")
+        .arg("echo")
+        .arg("scoped")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--show")
+        .arg("greet")
+        .assert()
+        .success()
+        .stdout(contains("[2]: greet\n").and(contains("  cmd: echo scoped\n")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(dir.as_ref())
+        .arg("--show")
+        .arg("--id")
+        .arg("1")
+        .assert()
+        .success()
+        .stdout("[1]: greet\n  scope: global\n  cmd: echo global\n");
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--show")
+        .arg("missing")
+        .assert()
+        .failure()
+        .stderr(contains("command not found: \"missing\""));
+
+    Ok(())
+}
+
+#[test]
+fn edit_export() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("greet")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--scope")
+        .arg(dir.as_ref())
+        .arg("greet")
+        .arg("echo")
+        .arg("scoped")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--edit")
+        .arg("--id")
+        .arg("1")
+        .arg("--set-cmd")
+        .arg("printf")
+        .assert()
+        .success()
+        .stdout(contains("[1]: greet\n").and(contains("  cmd: printf global\n")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--export")
+        .arg("--id")
+        .arg("2")
+        .arg("1")
+        .arg("--format")
+        .arg("toml")
+        .assert()
+        .success()
+        .stdout(contains("command = \"echo\"").and(contains("command = \"printf\"")));
+
+    Ok(())
+}
+
+#[test]
+fn requires_operation() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--list")
+        .arg("--id")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "option -i, --id requires operation <NAME>, -r, --remove, --edit, --export or --show",
+        ));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--fuzzy")
+        .arg("--id")
+        .arg("1")
+        .assert()
+        .failure()
+        .stderr(contains("options -i, --id and --fuzzy are incompatible"));
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+const DOC: &str = r#"
+version = 1
+
+[[commands]]
+name = "test1"
+command = "sh"
+args = ["-c", "echo $CXD_TEST1 $CXD_TEST2"]
+
+[commands.envs]
+CXD_TEST1 = "imported"
+CXD_TEST2 = "env"
+
+[[commands]]
+name = "test2"
+command = "echo"
+args = ["imported"]
+"#;
+
+fn write_doc(dir: &TempCacheDir, name: &str, contents: &str) -> anyhow::Result<String> {
+    let path = dir.as_ref().join(name);
+    std::fs::write(&path, contents)?;
+    Ok(path.to_string_lossy().into())
+}
+
+fn exec_output(dir: &TempCacheDir, name: &str) -> anyhow::Result<String> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd.arg(name).output()?;
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn import() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--import")
+        .arg(doc)
+        .assert()
+        .success()
+        .stdout(contains("Created test1").and(contains("2 created")));
+
+    assert_eq!(exec_output(&dir, "test1")?, "imported env\n");
+    assert_eq!(exec_output(&dir, "test2")?, "imported\n");
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let dir1 = TempCacheDir::new()?;
+    let doc = write_doc(&dir1, "doc.toml", DOC)?;
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir1.as_ref());
+    cmd1.arg("--import").arg(doc).assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir1.as_ref());
+    let exported = cmd2.arg("--export").output()?.stdout;
+
+    // Importing from stdin into a fresh cache
+    let dir2 = TempCacheDir::new()?;
+    let mut cmd3 = assert_cmd::Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd3.arg("--import")
+        .arg("-")
+        .write_stdin(exported.clone())
+        .assert()
+        .success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd4.arg("--export").assert().success().stdout(exported);
+
+    Ok(())
+}
+
+#[test]
+fn conflict() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test1")
+        .arg("echo")
+        .arg("existing")
+        .assert()
+        .success();
+
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--import")
+        .arg(doc)
+        .assert()
+        .failure()
+        .stderr(contains("command already exists: \"test1\""));
+
+    // Nothing was imported
+    assert_eq!(exec_output(&dir, "test1")?, "existing\n");
+    assert_eq!(exec_output(&dir, "test2")?, "");
+
+    Ok(())
+}
+
+#[test]
+fn skip_existing() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test1")
+        .arg("echo")
+        .arg("existing")
+        .assert()
+        .success();
+
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--import")
+        .arg("--skip-existing")
+        .arg(doc)
+        .assert()
+        .success()
+        .stdout(contains("Skipped test1").and(contains("1 created, 0 overwritten, 1 skipped")));
+
+    assert_eq!(exec_output(&dir, "test1")?, "existing\n");
+    assert_eq!(exec_output(&dir, "test2")?, "imported\n");
+
+    Ok(())
+}
+
+#[test]
+fn overwrite() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test1")
+        .arg("echo")
+        .arg("existing")
+        .assert()
+        .success();
+
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--import")
+        .arg("--overwrite")
+        .arg(doc)
+        .assert()
+        .success()
+        .stdout(contains("Overwrote test1"));
+
+    assert_eq!(exec_output(&dir, "test1")?, "imported env\n");
+
+    Ok(())
+}
+
+#[test]
+fn rename_suffix() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test1")
+        .arg("echo")
+        .arg("existing")
+        .assert()
+        .success();
+
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    for name in ["test1_new", "test1_new2"] {
+        let mut cmd2 = Command::cargo_bin("cxd")?;
+        cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd2.arg("--import")
+            .arg("--rename-suffix")
+            .arg("_new")
+            .arg(&doc)
+            .assert()
+            .success()
+            .stdout(contains(format!("Created {name}, renamed from test1")));
+    }
+
+    assert_eq!(exec_output(&dir, "test1")?, "existing\n");
+    assert_eq!(exec_output(&dir, "test1_new2")?, "imported env\n");
+
+    Ok(())
+}
+
+#[test]
+fn dry_run() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test1")
+        .arg("echo")
+        .arg("existing")
+        .assert()
+        .success();
+
+    let doc = write_doc(&dir, "doc.toml", DOC)?;
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--import")
+        .arg("--overwrite")
+        .arg("--dry-run")
+        .arg(doc)
+        .assert()
+        .success()
+        .stdout(
+            contains("Would overwrite test1")
+                .and(contains("Would create test2"))
+                .and(contains("no changes were made")),
+        );
+
+    assert_eq!(exec_output(&dir, "test1")?, "existing\n");
+    assert_eq!(exec_output(&dir, "test2")?, "");
+
+    Ok(())
+}
+
+#[test]
+fn incompatible_policies() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--import")
+        .arg("--skip-existing")
+        .arg("--overwrite")
+        .arg("doc.json")
+        .assert()
+        .failure()
+        .stderr(contains("incompatible"));
+
+    Ok(())
+}
+
+#[test]
+fn newer_version() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let doc = write_doc(
+        &dir,
+        "doc.json",
+        r#"{"version": 2, "commands": [], "new": 1}"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--import")
+        .arg(doc)
+        .assert()
+        .failure()
+        .stderr(contains("document version 2 is from a newer cxd"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let doc = write_doc(
+        &dir,
+        "doc.json",
+        r#"{"version": 1, "commands": [{"name": "test1"}]}"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--import")
+        .arg(doc)
+        .assert()
+        .failure()
+        .stderr(contains("invalid document").and(contains("command")));
+
+    Ok(())
+}
@@ -0,0 +1,337 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::str::contains;
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn json() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\tb")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("test one")
+        .arg("echo")
+        .arg("hi there")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("--dep")
+        .arg("test one")
+        .arg("test2")
+        .arg("ls | wc -l")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd3.arg("--list").arg("--format").arg("json").output()?;
+    assert!(output.status.success());
+
+    // Every field is present, even when unset
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        list,
+        serde_json::json!([
+            {
+                "id": 1,
+                "name": "test one",
+                "origin": null,
+                "scope": null,
+                "dir": "/tmp",
+                "shell": null,
+                "command": "echo",
+                "args": ["hi there", "{1}"],
+                "envs": {"CXD_TEST": "a\tb"},
+                "deps": [],
+                "steps": [],
+                "tags": [],
+                "description": "",
+            },
+            {
+                "id": 2,
+                "name": "test2",
+                "origin": null,
+                "scope": null,
+                "dir": null,
+                "shell": "",
+                "command": "ls | wc -l",
+                "args": [],
+                "envs": {},
+                "deps": ["test one"],
+                "steps": [],
+                "tags": [],
+                "description": "",
+            },
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tsv() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\tb")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("test one")
+        .arg("echo")
+        .arg("hi there")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("--dep")
+        .arg("test one")
+        .arg("test2")
+        .arg("ls | wc -l")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--list")
+        .arg("--format")
+        .arg("tsv")
+        .assert()
+        .success()
+        .stdout(
+            "1\ttest one\t\t/tmp\t\techo\t'hi there' '{1}'\tCXD_TEST=$'a\\\\tb'\t\t\n\
+             2\ttest2\t\t\t$SHELL\tls | wc -l\t\t\t\t\n",
+        );
+
+    Ok(())
+}
+
+#[test]
+fn null() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\tb")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("test one")
+        .arg("echo")
+        .arg("hi there")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("--dep")
+        .arg("test one")
+        .arg("test2")
+        .arg("ls | wc -l")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--list")
+        .arg("--format")
+        .arg("null")
+        .assert()
+        .success()
+        .stdout("test one\0test2\0");
+
+    Ok(())
+}
+
+#[test]
+fn invalid_format() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--list")
+        .arg("--format")
+        .arg("toml")
+        .assert()
+        .failure()
+        .stderr(contains("expected one of json, tsv, null"));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--list")
+        .arg("--format")
+        .arg("json")
+        .arg("--short")
+        .assert()
+        .failure()
+        .stderr(contains("are incompatible"));
+
+    Ok(())
+}
+
+/// Lists the names of commands matching `filters`, as seen from `dir`
+fn list_names(dir: &TempCacheDir, filters: &[&str]) -> anyhow::Result<String> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.current_dir(dir.as_ref()).arg("--list").arg("--short");
+    let output = cmd.args(filters).output()?;
+    assert!(output.status.success(), "{output:?}");
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn filters() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\tb")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("test one")
+        .arg("echo")
+        .arg("hi there")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("--dep")
+        .arg("test one")
+        .arg("test2")
+        .arg("ls | wc -l")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--add")
+        .arg("--cwd")
+        .arg("--env")
+        .arg("CXD_OTHER=1")
+        .arg("test3")
+        .arg("printf")
+        .arg("%s")
+        .arg("Hi")
+        .assert()
+        .success();
+
+    // Matches names, programs and args, ignoring case
+    assert_eq!(
+        list_names(&dir, &["--grep", "TEST"])?,
+        "test one\ntest2\ntest3\n"
+    );
+    assert_eq!(list_names(&dir, &["--grep", "wc -l"])?, "test2\n");
+    assert_eq!(list_names(&dir, &["--grep", "hi"])?, "test one\ntest3\n");
+    assert_eq!(list_names(&dir, &["--grep", "%"])?, "test3\n");
+
+    // Includes directories below, but never above
+    assert_eq!(list_names(&dir, &["--dir", "/tmp"])?, "test one\ntest3\n");
+    assert_eq!(list_names(&dir, &["--here"])?, "test3\n");
+
+    assert_eq!(list_names(&dir, &["--env", "CXD_TEST"])?, "test one\n");
+
+    // Every filter must match
+    assert_eq!(
+        list_names(&dir, &["--grep", "hi", "--env", "CXD_OTHER"])?,
+        "test3\n"
+    );
+    assert_eq!(list_names(&dir, &["--grep", "wc", "--here"])?, "");
+
+    Ok(())
+}
+
+#[test]
+fn filters_shadowed() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\tb")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("test one")
+        .arg("echo")
+        .arg("hi there")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("--dep")
+        .arg("test one")
+        .arg("test2")
+        .arg("ls | wc -l")
+        .assert()
+        .success();
+
+    std::fs::write(
+        dir.as_ref().join(".cxd.toml"),
+        "version = 1\n\n[[commands]]\nname = \"test2\"\ncommand = \"true\"\n",
+    )?;
+
+    // The cached `test2` matches, but is shadowed by the project's `test2`
+    assert_eq!(list_names(&dir, &["--grep", "wc"])?, "");
+    assert_eq!(list_names(&dir, &["--grep", "true"])?, "test2\n");
+
+    Ok(())
+}
+
+#[test]
+fn filters_require_list() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--history")
+        .arg("--grep")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("requires"));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--list")
+        .arg("--here")
+        .arg("--dir")
+        .arg("/tmp")
+        .assert()
+        .failure()
+        .stderr(contains("are incompatible"));
+
+    Ok(())
+}
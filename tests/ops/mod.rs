@@ -1,3 +1,25 @@
 mod add;
+mod again;
+mod dep;
+mod desc;
+mod dry_run;
+mod edit;
+mod exec;
+mod export;
+mod fuzzy;
 mod help;
+mod history;
+mod id;
+mod import;
+mod list;
+mod pick;
+mod project;
+mod quote;
 mod remove;
+mod rename;
+mod scope;
+mod shell;
+mod step;
+mod tag;
+mod template;
+mod trust;
@@ -0,0 +1,169 @@
+use std::process::Command;
+
+use assert_cmd::{cargo::cargo_bin, prelude::*};
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+/// Runs `cxd --pick` from `dir` on a pseudo terminal, typing `keys`
+///
+/// The keys are typed once the picker has had time to start, as until it switches the terminal
+/// to raw mode, control keys such as `^c` are handled by the terminal instead.
+fn pick(dir: &TempCacheDir, keys: &str) -> anyhow::Result<assert_cmd::assert::Assert> {
+    let mut cmd = Command::new("sh");
+    cmd.env("CXD_CACHE_DIR", dir.as_ref()).env("KEYS", keys);
+    cmd.current_dir(dir.as_ref()).arg("-c").arg(format!(
+        "(sleep 0.5; printf %s \"$KEYS\") | script -qec '{} --pick' /dev/null",
+        cargo_bin("cxd").display()
+    ));
+    Ok(assert_cmd::Command::from_std(cmd)
+        .timeout(std::time::Duration::from_secs(10))
+        .assert())
+}
+
+#[test]
+fn run_filtered() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for (name, scope) in [
+        ("build", None),
+        ("test", None),
+        ("local", Some(dir.as_ref())),
+    ] {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.arg("--add");
+        if let Some(scope) = scope {
+            cmd.arg("--scope").arg(scope);
+        }
+        cmd.arg(name).arg("echo").arg(name).assert().success();
+    }
+
+    pick(&dir, "bld\r")?
+        .success()
+        .stdout(contains("cxd build\r\nbuild\r\n"));
+
+    Ok(())
+}
+
+#[test]
+fn scoped_first() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for (name, scope) in [
+        ("build", None),
+        ("test", None),
+        ("local", Some(dir.as_ref())),
+    ] {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.arg("--add");
+        if let Some(scope) = scope {
+            cmd.arg("--scope").arg(scope);
+        }
+        cmd.arg(name).arg("echo").arg(name).assert().success();
+    }
+
+    // Without a filter, the command scoped to the CWD is selected
+    pick(&dir, "\r")?
+        .success()
+        .stdout(contains("cxd local\r\nlocal\r\n"));
+
+    Ok(())
+}
+
+#[test]
+fn delete() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for (name, scope) in [
+        ("build", None),
+        ("test", None),
+        ("local", Some(dir.as_ref())),
+    ] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add");
+        if let Some(scope) = scope {
+            cmd1.arg("--scope").arg(scope);
+        }
+        cmd1.arg(name).arg("echo").arg(name).assert().success();
+    }
+
+    // Switches to keys, selects `build`, deletes it, then quits the reopened picker
+    pick(&dir, "\tjdyq")?
+        .success()
+        .stdout(contains("Removed build"));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--list")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("test\nlocal\n");
+
+    Ok(())
+}
+
+#[test]
+fn delete_while_filtering() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for name in ["build", "test"] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add")
+            .arg(name)
+            .arg("echo")
+            .arg(name)
+            .assert()
+            .success();
+    }
+
+    // ^d deletes without switching to keys, and a typed d is part of the filter
+    pick(&dir, "tsd\x7f\x04y\x03")?
+        .success()
+        .stdout(contains("Removed test"));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--list")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("build\n");
+
+    Ok(())
+}
+
+#[test]
+fn no_terminal() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    for (name, scope) in [
+        ("build", None),
+        ("test", None),
+        ("local", Some(dir.as_ref())),
+    ] {
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add");
+        if let Some(scope) = scope {
+            cmd1.arg("--scope").arg(scope);
+        }
+        cmd1.arg(name).arg("echo").arg(name).assert().success();
+    }
+
+    // A new session has no controlling terminal
+    let mut cmd2 = Command::new("setsid");
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg(cargo_bin("cxd"))
+        .arg("--pick")
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(contains("no terminal to pick from"));
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+const PROJECT: &str = r#"
+version = 1
+
+[[commands]]
+name = "test"
+command = "echo"
+args = ["project"]
+
+[[commands]]
+name = "where"
+command = "pwd"
+dir = "sub"
+
+[[commands]]
+name = "nested"
+command = "echo"
+args = ["nested"]
+scope = "sub"
+"#;
+
+#[test]
+fn shadow_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    std::fs::write(dir.as_ref().join(".cxd.toml"), PROJECT)?;
+    std::fs::create_dir(dir.as_ref().join("sub"))?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust")
+        .arg(dir.as_ref().join(".cxd.toml"))
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("cache")
+        .assert()
+        .success();
+
+    // From a subdirectory of the project
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref().join("sub"))
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("project\n");
+
+    // Outside of the project
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir("/")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("cache\n");
+
+    Ok(())
+}
+
+#[test]
+fn relative_paths() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    std::fs::write(dir.as_ref().join(".cxd.toml"), PROJECT)?;
+    std::fs::create_dir(dir.as_ref().join("sub"))?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust")
+        .arg(dir.as_ref().join(".cxd.toml"))
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("cache")
+        .assert()
+        .success();
+
+    let sub = dir.as_ref().join("sub");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("where")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", sub.to_str().unwrap()));
+
+    // Scoped to the subdirectory
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(dir.as_ref())
+        .arg("nested")
+        .assert()
+        .failure()
+        .stderr(contains("command not found"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.current_dir(&sub)
+        .arg("nested")
+        .assert()
+        .success()
+        .stdout("nested\n");
+
+    Ok(())
+}
+
+#[test]
+fn list_origin() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    std::fs::write(dir.as_ref().join(".cxd.toml"), PROJECT)?;
+    std::fs::create_dir(dir.as_ref().join("sub"))?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust")
+        .arg(dir.as_ref().join(".cxd.toml"))
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("cache")
+        .assert()
+        .success();
+
+    let file = dir.as_ref().join(".cxd.toml");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(
+            contains(format!("[-]: test\n  origin: {}", file.to_str().unwrap()))
+                .and(contains("[1]: test").not())
+                .and(contains("echo cache").not()),
+        );
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir("/")
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(contains("[1]: test").and(contains("origin").not()));
+
+    Ok(())
+}
+
+#[test]
+fn read_only() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    std::fs::write(dir.as_ref().join(".cxd.toml"), PROJECT)?;
+    std::fs::create_dir(dir.as_ref().join("sub"))?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust")
+        .arg(dir.as_ref().join(".cxd.toml"))
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("cache")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("--remove")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("which is read only"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(dir.as_ref())
+        .arg("--edit")
+        .arg("test")
+        .arg("--set-cmd")
+        .arg("true")
+        .assert()
+        .failure()
+        .stderr(contains("which is read only"));
+
+    // The cache command is still there
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.current_dir("/")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("cache\n");
+
+    Ok(())
+}
+
+#[test]
+fn invalid_file() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    std::fs::write(dir.as_ref().join(".cxd.toml"), "version = 1\nbogus = 2\n")?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.current_dir(dir.as_ref())
+        .arg("--list")
+        .assert()
+        .failure()
+        .stderr(contains("invalid project file").and(contains(".cxd.toml")));
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn list_round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\nb")
+        .arg("test")
+        .arg("printf")
+        .arg("%s|")
+        .arg("a b")
+        .arg("it's")
+        .arg("")
+        .arg("x\ny")
+        .assert()
+        .success()
+        .stdout(
+            contains("  env: CXD_TEST=$'a\\nb'\n")
+                .and(contains("  cmd: printf '%s|' 'a b' 'it'\\''s' '' $'x\\ny'")),
+        );
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    let list = String::from_utf8(cmd2.arg("--list").output()?.stdout)?;
+    // Each value stays on one line
+    assert_eq!(list.lines().count(), 5, "{list}");
+    let line = list
+        .lines()
+        .find_map(|l| l.strip_prefix("  cmd: "))
+        .expect("cmd line listed");
+
+    // Pasting the displayed line into a shell runs the same arguments
+    let mut cmd3 = Command::new("bash");
+    cmd3.arg("-c").arg(line);
+    cmd3.assert().success().stdout("a b|it's||x\ny|");
+
+    Ok(())
+}
+
+#[test]
+fn editor_round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\nb")
+        .arg("test")
+        .arg("printf")
+        .arg("%s|")
+        .arg("a b")
+        .arg("it's")
+        .arg("")
+        .arg("x\ny")
+        .assert()
+        .success()
+        .stdout(
+            contains("  env: CXD_TEST=$'a\\nb'\n")
+                .and(contains("  cmd: printf '%s|' 'a b' 'it'\\''s' '' $'x\\ny'")),
+        );
+
+    // Quoted values parse back unchanged
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env("VISUAL", "true")
+        .arg("--edit")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("No changes"));
+
+    // Appends `arg: "c d"` and `arg: $'e\tf'`
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.env(
+        "VISUAL",
+        "sed -i -e '$a arg: \"c d\"' -e \"\\$a arg: \\$'e\\\\\\\\tf'\"",
+    )
+    .arg("--edit")
+    .arg("test")
+    .assert()
+    .success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("test")
+        .assert()
+        .success()
+        .stdout("a b|it's||x\ny|c d|e\tf|");
+
+    Ok(())
+}
+
+#[test]
+fn editor_unquoted_spaces() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=a\nb")
+        .arg("test")
+        .arg("printf")
+        .arg("%s|")
+        .arg("a b")
+        .arg("it's")
+        .arg("")
+        .arg("x\ny")
+        .assert()
+        .success()
+        .stdout(
+            contains("  env: CXD_TEST=$'a\\nb'\n")
+                .and(contains("  cmd: printf '%s|' 'a b' 'it'\\''s' '' $'x\\ny'")),
+        );
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env("VISUAL", "sed -i -e '$a arg: c d'")
+        .arg("--edit")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("quote values containing spaces"));
+
+    Ok(())
+}
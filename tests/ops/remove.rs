@@ -108,3 +108,28 @@ fn id_long() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn removed_args() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("saved")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("--remove")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("echo saved"));
+
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn rename() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=kept")
+        .arg("--tag")
+        .arg("ci")
+        .arg("build")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo $CXD_TEST; pwd")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--record").arg("build").assert().success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--rename")
+        .arg("build")
+        .arg("make")
+        .assert()
+        .success()
+        .stdout(contains("[1]: make\n").and(contains("  tags: ci\n")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("make").assert().success().stdout(contains("kept"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("build").assert().failure();
+
+    // Recorded runs follow the command
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--history")
+        .arg("make")
+        .assert()
+        .success()
+        .stdout(contains("]: make\n"));
+
+    Ok(())
+}
+
+#[test]
+fn rename_existing() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=kept")
+        .arg("--tag")
+        .arg("ci")
+        .arg("build")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo $CXD_TEST; pwd")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--record").arg("build").assert().success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add").arg("make").arg("echo").assert().success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--rename")
+        .arg("build")
+        .arg("make")
+        .assert()
+        .failure()
+        .stderr(contains("command already exists: \"make\""));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--rename")
+        .arg("missing")
+        .arg("other")
+        .assert()
+        .failure()
+        .stderr(contains("command not found: \"missing\""));
+
+    Ok(())
+}
+
+#[test]
+fn rename_dep() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let project = TempCacheDir::new()?;
+    let sibling = TempCacheDir::new()?;
+
+    for (cwd, output) in [(&project, "project"), (&sibling, "sibling")] {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.current_dir(cwd.as_ref())
+            .arg("--add")
+            .arg("--local")
+            .arg("build")
+            .arg("echo")
+            .arg(output)
+            .assert()
+            .success();
+
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.current_dir(cwd.as_ref())
+            .arg("--add")
+            .arg("--local")
+            .arg("--dep")
+            .arg("build")
+            .arg("test")
+            .arg("echo")
+            .arg("test")
+            .assert()
+            .success();
+    }
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.current_dir(project.as_ref())
+        .arg("--rename")
+        .arg("build")
+        .arg("compile")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(project.as_ref())
+        .arg("--show")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("  deps: compile\n"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(project.as_ref())
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("project\ntest\n");
+
+    // Dependents outside its scope are left alone
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(sibling.as_ref())
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("sibling\ntest\n");
+
+    Ok(())
+}
+
+#[test]
+fn copy() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let sibling = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=kept")
+        .arg("--tag")
+        .arg("ci")
+        .arg("build")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo $CXD_TEST; pwd")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--record").arg("build").assert().success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--copy")
+        .arg("build")
+        .arg("build-sibling")
+        .arg("--dir")
+        .arg(sibling.as_ref())
+        .assert()
+        .success()
+        .stdout(
+            contains("[2]: build-sibling\n")
+                .and(contains("  env: CXD_TEST=kept\n"))
+                .and(contains("  tags: ci\n")),
+        );
+
+    // Recorded runs stay with the original
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--history")
+        .arg("build-sibling")
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.current_dir(sibling.as_ref())
+        .arg("build-sibling")
+        .assert()
+        .success()
+        .stdout(format!("kept\n{}\n", sibling.as_ref().display()));
+
+    // Both keep the tag
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--list")
+        .arg("--tag")
+        .arg("ci")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("build\nbuild-sibling\n");
+
+    let mut cmd7 = Command::cargo_bin("cxd")?;
+    cmd7.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd7.arg("--copy")
+        .arg("build")
+        .arg("build-sibling")
+        .arg("--dir")
+        .arg(sibling.as_ref())
+        .assert()
+        .failure()
+        .stderr(contains("command already exists: \"build-sibling\""));
+
+    Ok(())
+}
+
+#[test]
+fn copy_to_sibling() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let project = TempCacheDir::new()?;
+    let sibling = TempCacheDir::new()?;
+    let sibling_path = std::fs::canonicalize(sibling.as_ref())?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.current_dir(project.as_ref())
+        .arg("--add")
+        .arg("--local")
+        .arg("build")
+        .arg("pwd")
+        .assert()
+        .success();
+
+    // The same name, relative to the CWD, as the copy is scoped to the sibling
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(project.as_ref())
+        .arg("--copy")
+        .arg("build")
+        .arg("build")
+        .arg("--dir")
+        .arg(format!(
+            "../{}",
+            sibling_path.file_name().unwrap().to_string_lossy()
+        ))
+        .assert()
+        .success()
+        .stdout(contains(format!("  scope: {}\n", sibling_path.display())));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(sibling.as_ref())
+        .arg("build")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", sibling_path.display()));
+
+    // The original still runs from its own project
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(project.as_ref())
+        .arg("build")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            std::fs::canonicalize(project.as_ref())?.display()
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn requires_two_names() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--copy")
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(contains("copy requires 2 arguments, found 1"));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--rename")
+        .arg("--dir")
+        .arg("/tmp")
+        .arg("build")
+        .arg("make")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "option -d, --dir requires operation -a, --add, --add-step, --copy or --list",
+        ));
+
+    Ok(())
+}
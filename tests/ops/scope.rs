@@ -0,0 +1,254 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn nearest_scope() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let outer = dir.as_ref().join("outer");
+    let inner = outer.join("inner");
+    std::fs::create_dir_all(&inner)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--scope")
+        .arg(&outer)
+        .arg("test")
+        .arg("echo")
+        .arg("outer")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(&inner)
+        .arg("--add")
+        .arg("--local")
+        .arg("test")
+        .arg("echo")
+        .arg("inner")
+        .assert()
+        .success();
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(&inner)
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("inner"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.current_dir(&outer)
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("outer"));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("global"));
+
+    Ok(())
+}
+
+#[test]
+fn out_of_scope() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let scoped = dir.as_ref().join("scoped");
+    std::fs::create_dir(&scoped)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--scope")
+        .arg(&scoped)
+        .arg("test")
+        .arg("echo")
+        .arg("hi")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("command not found"));
+
+    Ok(())
+}
+
+#[test]
+fn unique_within_scope() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let first = dir.as_ref().join("first");
+    let second = dir.as_ref().join("second");
+    std::fs::create_dir(&first)?;
+    std::fs::create_dir(&second)?;
+
+    for scope in [&first, &second] {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.arg("--add")
+            .arg("--scope")
+            .arg(scope)
+            .arg("test")
+            .arg("echo")
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--add")
+        .arg("--scope")
+        .arg(&first)
+        .arg("test")
+        .arg("echo")
+        .assert()
+        .failure()
+        .stderr(contains("command already exists"));
+
+    Ok(())
+}
+
+#[test]
+fn remove_nearest() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let scoped = dir.as_ref().join("scoped");
+    std::fs::create_dir(&scoped)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("global")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(&scoped)
+        .arg("--add")
+        .arg("--local")
+        .arg("test")
+        .arg("echo")
+        .arg("scoped")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(&scoped)
+        .arg("--remove")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains(scoped.to_str().unwrap()));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(&scoped)
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("global"));
+
+    Ok(())
+}
+
+#[test]
+fn list_scope() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.current_dir(dir.as_ref())
+        .arg("--add")
+        .arg("--local")
+        .arg("test")
+        .arg("echo")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("other")
+        .arg("echo")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--list").assert().success().stdout(
+        contains(format!("scope: {}", dir.as_ref().to_str().unwrap()))
+            .and(contains("scope: global")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_unscoped_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute_batch(
+        r#"
+        CREATE TABLE cxd_cmd (
+            id      INTEGER PRIMARY KEY AUTOINCREMENT,
+            name    TEXT NOT NULL,
+            cmd     TEXT NOT NULL,
+            dir     TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(name)
+        );
+        CREATE TABLE cxd_arg (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            data        TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        INSERT INTO cxd_cmd (name, cmd, dir) VALUES ('test', 'echo', '');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (1, 'upgraded');
+    "#,
+    )?;
+    drop(c);
+
+    // Not written by cxd, so must be trusted first
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("upgraded"));
+
+    Ok(())
+}
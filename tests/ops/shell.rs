@@ -0,0 +1,174 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn pipe() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--shell=sh")
+        .arg("test")
+        .arg("printf \"%s\\n\" two one | sort")
+        .assert()
+        .success()
+        .stdout(contains(
+            "shell: sh\n  script: 'printf \"%s\\n\" two one | sort'",
+        ));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("test").assert().success().stdout("one\ntwo\n");
+
+    Ok(())
+}
+
+#[test]
+fn separate_value() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    // Known shells and paths to executables are taken as the value of --shell
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--shell")
+        .arg("sh")
+        .arg("test")
+        .arg("echo $0 \"$@\" | tr a-z A-Z")
+        .assert()
+        .success()
+        .stdout(contains("[1]: test\n").and(contains("  shell: sh\n")));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--shell")
+        .arg("/bin/sh")
+        .arg("--cwd")
+        .arg("other")
+        .arg("pwd")
+        .assert()
+        .success()
+        .stdout(contains("[2]: other\n").and(contains("  shell: /bin/sh\n")));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .arg("a")
+        .assert()
+        .success()
+        .stdout("TEST A\n");
+
+    Ok(())
+}
+
+#[test]
+fn positional_args() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    // Saved args come before extra args, the script itself is never expanded
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--shell")
+        .arg("test")
+        .arg("echo \"$0:{1}\" \"$@\" > out.txt; cat out.txt")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref())
+        .env("SHELL", "sh")
+        .current_dir(dir.as_ref());
+    cmd2.arg("test")
+        .arg("a")
+        .arg("b c")
+        .assert()
+        .success()
+        .stdout("test:{1} a b c\n");
+
+    Ok(())
+}
+
+#[test]
+fn default_shell() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--shell")
+        .arg("test")
+        .arg("echo $0")
+        .assert()
+        .success()
+        .stdout(contains("shell: $SHELL"));
+
+    // Falls back to sh when $SHELL is unset
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref()).env_remove("SHELL");
+    cmd2.arg("test").assert().success().stdout("test\n");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref())
+        .env("SHELL", "/nonexistent/shell");
+    cmd3.arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("exec test"));
+
+    Ok(())
+}
+
+#[test]
+fn edit_shell() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("a|b")
+        .assert()
+        .success();
+
+    // Turning a program into a script
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref()).env(
+        "VISUAL",
+        "sed -i -e 's/^cmd: echo$/shell: sh\\ncmd: \"echo $1 | tr a b\"/'",
+    );
+    cmd2.arg("--edit")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("shell: sh"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test").assert().success().stdout("b|b\n");
+
+    Ok(())
+}
+
+#[test]
+fn shell_requires_add() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .arg("--shell=bash")
+        .assert()
+        .failure()
+        .stderr(contains("option --shell requires operation -a, --add"));
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn stop_on_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("one")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo two; exit 2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .arg("three")
+        .assert()
+        .success()
+        .stdout(contains("step: sh -c 'echo two; exit 2'").and(contains("step: echo three")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("test").assert().code(2).stdout("one\ntwo\n");
+
+    Ok(())
+}
+
+#[test]
+fn keep_going() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("one")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo two; exit 2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .arg("three")
+        .assert()
+        .success()
+        .stdout(contains("step: sh -c 'echo two; exit 2'").and(contains("step: echo three")));
+
+    // Extra args are appended to the last step
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--keep-going")
+        .arg("test")
+        .arg("extra")
+        .assert()
+        .code(2)
+        .stdout("one\ntwo\nthree extra\n");
+
+    // Always supervised, so recorded with the first failure
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history")
+        .assert()
+        .success()
+        .stdout(contains("exit: 2"));
+
+    Ok(())
+}
+
+#[test]
+fn inherit_dir_env() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let sub = dir.as_ref().join("sub");
+    std::fs::create_dir(&sub)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("-d")
+        .arg(dir.as_ref())
+        .arg("-e")
+        .arg("A=cmd")
+        .arg("-e")
+        .arg("B=cmd")
+        .arg("test")
+        .arg("true")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("pwd; echo $A $B")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("-d")
+        .arg(&sub)
+        .arg("-e")
+        .arg("B=step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("pwd; echo $A $B")
+        .assert()
+        .success()
+        .stdout(
+            contains(format!("    dir: {}", sub.to_str().unwrap()))
+                .and(contains("    env: B=step")),
+        );
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("test").assert().success().stdout(format!(
+        "{}\ncmd cmd\n{}\ncmd step\n",
+        dir.as_ref().to_str().unwrap(),
+        sub.to_str().unwrap()
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn edit_keeps_steps() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("one")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo two; exit 2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .arg("three")
+        .assert()
+        .success()
+        .stdout(contains("step: sh -c 'echo two; exit 2'").and(contains("step: echo three")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--edit")
+        .arg("test")
+        .arg("--set-args")
+        .arg("edited")
+        .assert()
+        .success();
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("test").assert().code(2).stdout("edited\ntwo\n");
+
+    Ok(())
+}
+
+#[test]
+fn removed_steps() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("one")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo two; exit 2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .arg("three")
+        .assert()
+        .success()
+        .stdout(contains("step: sh -c 'echo two; exit 2'").and(contains("step: echo three")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--remove").arg("test").assert().success();
+
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    for table in ["cxd_step", "cxd_step_arg", "cxd_step_env"] {
+        let count: i64 = c.query_row(&format!("SELECT COUNT(*) FROM {table}"), (), |row| {
+            row.get(0)
+        })?;
+        assert_eq!(count, 0, "{table} not empty");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("one")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add-step")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo two; exit 2")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .arg("three")
+        .assert()
+        .success()
+        .stdout(contains("step: sh -c 'echo two; exit 2'").and(contains("step: echo three")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    let exported = cmd4.arg("--export").arg("--format").arg("toml").output()?;
+    assert!(exported.status.success());
+    let file = dir.as_ref().join("export.toml");
+    std::fs::write(&file, &exported.stdout)?;
+
+    let other = TempCacheDir::new()?;
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", other.as_ref());
+    cmd5.arg("--import").arg(&file).assert().success();
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", other.as_ref());
+    cmd6.arg("test").assert().code(2).stdout("one\ntwo\n");
+
+    Ok(())
+}
+
+#[test]
+fn add_step_not_found() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--add-step")
+        .arg("test")
+        .arg("echo")
+        .assert()
+        .failure()
+        .stderr(contains("command not found: \"test\""));
+
+    Ok(())
+}
@@ -0,0 +1,426 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn list_tagged() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("lint")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .arg("echo")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    // A --tag after <NAME> belongs to the command
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .arg("--tag")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci\n").and(contains("  cmd: echo test --tag")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--list")
+        .arg("--tag")
+        .arg("ci")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("lint\ntest\n");
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--list")
+        .arg("--tag")
+        .arg("ci")
+        .arg("--tag")
+        .arg("lint")
+        .assert()
+        .failure()
+        .stderr(contains("--tag may only be given once with --list"));
+
+    Ok(())
+}
+
+#[test]
+fn tag_untag() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("lint")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .arg("echo")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    // A --tag after <NAME> belongs to the command
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .arg("--tag")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci\n").and(contains("  cmd: echo test --tag")));
+
+    // Existing and repeated tags are kept once
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--tag")
+        .arg("lint")
+        .arg("release")
+        .arg("ci")
+        .arg("release")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint release\n"));
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--untag")
+        .arg("lint")
+        .arg("ci")
+        .arg("lint")
+        .arg("debug")
+        .assert()
+        .success()
+        .stdout(contains("  tags: release\n"));
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--list")
+        .arg("--tag")
+        .arg("ci")
+        .arg("--short")
+        .assert()
+        .success()
+        .stdout("test\n");
+
+    let mut cmd7 = Command::cargo_bin("cxd")?;
+    cmd7.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd7.arg("--tag")
+        .arg("lint")
+        .arg("two words")
+        .assert()
+        .failure()
+        .stderr(contains("tags must not be empty or contain whitespace"));
+
+    Ok(())
+}
+
+#[test]
+fn run_tag() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("lint")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .arg("echo")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    // A --tag after <NAME> belongs to the command
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .arg("--tag")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci\n").and(contains("  cmd: echo test --tag")));
+
+    // In the order added, with dependencies first
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--run-tag")
+        .arg("ci")
+        .assert()
+        .success()
+        .stdout("lint\nbuild\ntest --tag\n")
+        .stderr("cxd lint\ncxd test\n");
+
+    // Each tagged command is recorded
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--history").assert().success().stdout(
+        contains("]: lint")
+            .and(contains("]: test"))
+            .and(contains("]: build").not()),
+    );
+
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd6.arg("--run-tag")
+        .arg("release")
+        .assert()
+        .failure()
+        .stderr(contains("no commands tagged \"release\""));
+
+    Ok(())
+}
+
+#[test]
+fn run_tag_once() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    // `all` runs `late` first, though it was added later
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--tag")
+        .arg("x")
+        .arg("--dep")
+        .arg("late")
+        .arg("all")
+        .arg("echo")
+        .arg("all")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("x")
+        .arg("late")
+        .arg("echo")
+        .arg("late")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--run-tag")
+        .arg("x")
+        .arg("--print-shell")
+        .assert()
+        .success()
+        .stdout("echo late &&\necho all\n");
+
+    Ok(())
+}
+
+#[test]
+fn run_tag_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("lint")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .arg("echo")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    // A --tag after <NAME> belongs to the command
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .arg("--tag")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci\n").and(contains("  cmd: echo test --tag")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--edit")
+        .arg("lint")
+        .arg("--set-cmd")
+        .arg("false")
+        .assert()
+        .success();
+
+    // The first failure stops the rest
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("--run-tag").arg("ci").assert().code(1).stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("build")
+        .arg("echo")
+        .arg("build")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("--tag")
+        .arg("lint")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .arg("echo")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    // A --tag after <NAME> belongs to the command
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--add")
+        .arg("--dep")
+        .arg("build")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("test")
+        .arg("--tag")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci\n").and(contains("  cmd: echo test --tag")));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    let output = cmd4.arg("--export").arg("lint").output()?;
+    assert!(output.status.success());
+    let document = String::from_utf8(output.stdout)?;
+    assert!(document.contains("\"tags\": [\n        \"ci\",\n        \"lint\"\n      ]"));
+
+    let dir2 = TempCacheDir::new()?;
+    let mut cmd5 = assert_cmd::Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd5.arg("--import")
+        .arg("-")
+        .write_stdin(document)
+        .assert()
+        .success();
+
+    // Tags survive an edit which doesn't touch them
+    let mut cmd6 = Command::cargo_bin("cxd")?;
+    cmd6.env("CXD_CACHE_DIR", dir2.as_ref());
+    cmd6.env("VISUAL", "sed -i -e '$a arg: again'")
+        .arg("--edit")
+        .arg("lint")
+        .assert()
+        .success()
+        .stdout(contains("  tags: ci lint\n"));
+
+    Ok(())
+}
+
+#[test]
+fn requires_add_or_list() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--remove")
+        .arg("--tag")
+        .arg("ci")
+        .arg("lint")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "option --tag requires operation -a, --add or --list",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,365 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::str::contains;
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn positional() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("-p")
+        .arg("{1}")
+        .arg("second={2}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("first")
+        .arg("two")
+        .arg("extra")
+        .assert()
+        .success()
+        .stdout(contains("-p first second=two extra\n"));
+
+    Ok(())
+}
+
+#[test]
+fn all_args() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("sh")
+        .arg("-c")
+        .arg("echo $# \"$@\"")
+        .arg("sh")
+        .arg("{@}")
+        .arg("joined={@}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("a")
+        .arg("b")
+        .assert()
+        .success()
+        .stdout(contains("3 a b joined=a b\n"));
+
+    Ok(())
+}
+
+#[test]
+fn builtins() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("{name}")
+        .arg("{cwd}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains(format!("test {}", dir.as_ref().to_str().unwrap())));
+
+    Ok(())
+}
+
+#[test]
+fn git_root() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let nested = dir.as_ref().join("repo").join("nested");
+    std::fs::create_dir_all(&nested)?;
+    std::fs::create_dir(dir.as_ref().join("repo").join(".git"))?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--dir")
+        .arg("{git_root}")
+        .arg("test")
+        .arg("pwd")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(&nested)
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("repo\n"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("unresolved placeholder {git_root}"));
+
+    Ok(())
+}
+
+#[test]
+fn env_default() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST={CXD_INPUT:-fallback}")
+        .arg("test")
+        .arg("printenv")
+        .arg("CXD_TEST")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.env_remove("CXD_INPUT")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("fallback"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.env("CXD_INPUT", "given")
+        .arg("test")
+        .assert()
+        .success()
+        .stdout(contains("given"));
+
+    Ok(())
+}
+
+#[test]
+fn literal_braces() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("{}")
+        .arg("{{.Names}}")
+        .arg("${HOME}")
+        .arg("{print $1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("{} {{.Names}} ${HOME} {print $1}"));
+
+    Ok(())
+}
+
+#[test]
+fn awk_program() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("awk")
+        .arg("\\{print}")
+        .arg("{print $1}")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("file")
+        .assert()
+        .success()
+        .stdout("awk {print} {print $1} file\n");
+
+    Ok(())
+}
+
+#[test]
+fn jq_filter() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("jq")
+        .arg("\\{id}")
+        .arg("{id, name}")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("file")
+        .assert()
+        .success()
+        .stdout("jq {id} {id, name} file\n");
+
+    Ok(())
+}
+
+#[test]
+fn docker_format() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("docker")
+        .arg("ps")
+        .arg("--format")
+        .arg("{{.Names}}\t{{ .Status }}{{if .Ports}} {{.Ports}}{{end}}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("-a")
+        .assert()
+        .success()
+        .stdout("docker ps --format {{.Names}}\t{{ .Status }}{{if .Ports}} {{.Ports}}{{end}} -a\n");
+
+    Ok(())
+}
+
+#[test]
+fn unknown_placeholder() -> anyhow::Result<()> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("{nope}")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "unknown placeholder {nope}, write \\{nope} to pass it as is",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn escaped_placeholder() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    // Only placeholder shaped braces are escaped, so `grep` intervals keep their backslashes
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("\\{1}")
+        .arg("a\\{2\\}")
+        .arg("{1}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("first")
+        .assert()
+        .success()
+        .stdout("{1} a\\{2\\} first\n");
+
+    Ok(())
+}
+
+#[test]
+fn missing_position() -> anyhow::Result<()> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("{1}")
+        .arg("{3}")
+        .assert()
+        .failure()
+        .stderr(contains("{3} is used without {2}"));
+
+    Ok(())
+}
+
+#[test]
+fn unresolved_position() -> anyhow::Result<()> {
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("{1}")
+        .arg("{2}")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd2.arg("test")
+        .arg("one")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "unresolved placeholder {2} in \"test\": requires at least 2 arguments, found 1",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+#[test]
+fn foreign_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--list").assert().success();
+
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute_batch(
+        r#"
+        INSERT INTO cxd_cmd (name, cmd, dir) VALUES ('test', 'echo', '');
+        INSERT INTO cxd_arg (cmd_id, position, data) VALUES (1, 0, 'foreign');
+    "#,
+    )?;
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("test")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("is not trusted").and(contains("cxd --trust")));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--trust")
+        .assert()
+        .success()
+        .stdout(contains("Trusted"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("test").assert().success().stdout("foreign\n");
+
+    Ok(())
+}
+
+#[test]
+fn write_untrusted() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--list").assert().success();
+
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute_batch(
+        r#"
+        INSERT INTO cxd_cmd (name, cmd, dir) VALUES ('test', 'echo', '');
+        INSERT INTO cxd_arg (cmd_id, position, data) VALUES (1, 0, 'foreign');
+    "#,
+    )?;
+
+    // Adding to a foreign cache doesn't trust it
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--add")
+        .arg("other")
+        .arg("echo")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("other")
+        .assert()
+        .failure()
+        .stderr(contains("is not trusted"));
+
+    Ok(())
+}
+
+#[test]
+fn changed_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("mine")
+        .assert()
+        .success();
+
+    // Edits by cxd keep it trusted
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("test")
+        .arg("--set-args")
+        .arg("edited")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test").assert().success().stdout("edited\n");
+
+    // Changed behind cxd's back
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute("UPDATE cxd_arg SET data = 'tampered'", ())?;
+    drop(c);
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("test")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("has changed since it was trusted"));
+
+    Ok(())
+}
+
+#[test]
+fn project_file() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let file = dir.as_ref().join(".cxd.toml");
+    std::fs::write(
+        &file,
+        "version = 1\n[[commands]]\nname = \"test\"\ncommand = \"echo\"\nargs = [\"project\"]\n",
+    )?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "run `cxd --trust {}`",
+            file.to_str().unwrap()
+        )));
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.current_dir(dir.as_ref())
+        .arg("--trust")
+        .arg(".cxd.toml")
+        .assert()
+        .success();
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .success()
+        .stdout("project\n");
+
+    std::fs::write(
+        &file,
+        "version = 1\n[[commands]]\nname = \"test\"\ncommand = \"echo\"\nargs = [\"changed\"]\n",
+    )?;
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.current_dir(dir.as_ref())
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("has changed since it was trusted"));
+
+    Ok(())
+}
+
+#[test]
+fn untrust() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add").arg("test").arg("echo").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--untrust")
+        .assert()
+        .success()
+        .stdout(contains("Untrusted"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("is not trusted"));
+
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--untrust")
+        .assert()
+        .success()
+        .stdout(contains("was not trusted"));
+
+    Ok(())
+}
+
+#[test]
+fn trust_file() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let trust_file = dir.as_ref().join("custom.trust");
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.env("CXD_TRUST_FILE", &trust_file)
+        .arg("--add")
+        .arg("test")
+        .arg("echo")
+        .assert()
+        .success();
+
+    let contents = std::fs::read_to_string(&trust_file)?;
+    assert!(contents.ends_with(&format!(
+        "  {}\n",
+        dir.as_ref().join("cxd.cache").to_str().unwrap()
+    )));
+    assert!(!dir.as_ref().join("cxd.trust").exists());
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writes() -> anyhow::Result<()> {
+    // Each write keeps the cache trusted, however they interleave
+    for _ in 0..5 {
+        let dir = TempCacheDir::new()?;
+
+        let mut cmd1 = Command::cargo_bin("cxd")?;
+        cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd1.arg("--add")
+            .arg("first")
+            .arg("echo")
+            .arg("first")
+            .assert()
+            .success();
+
+        let children = (0..20)
+            .map(|i| {
+                Command::cargo_bin("cxd")?
+                    .env("CXD_CACHE_DIR", dir.as_ref())
+                    .arg("--add")
+                    .arg(format!("test{i}"))
+                    .arg("echo")
+                    .arg("one")
+                    .stdout(std::process::Stdio::null())
+                    .spawn()
+                    .map_err(anyhow::Error::from)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for mut child in children {
+            assert!(child.wait()?.success());
+        }
+
+        let mut cmd2 = Command::cargo_bin("cxd")?;
+        cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd2.arg("first").assert().success().stdout("first\n");
+    }
+
+    Ok(())
+}
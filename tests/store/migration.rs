@@ -0,0 +1,291 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+/// A cache as written by cxd 0.1.1, before schema versioning
+const LEGACY_CACHE: &str = r#"
+        CREATE TABLE cxd_cmd (
+            id      INTEGER PRIMARY KEY AUTOINCREMENT,
+            name    TEXT NOT NULL,
+            cmd     TEXT NOT NULL,
+            dir     TEXT NOT NULL,
+            UNIQUE(id)
+            UNIQUE(name)
+        );
+        CREATE TABLE cxd_arg (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            data        TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE TABLE cxd_env (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            cmd_id      INTEGER NOT NULL,
+            key         TEXT NOT NULL,
+            value       TEXT NOT NULL,
+            UNIQUE(id)
+            FOREIGN KEY(cmd_id) REFERENCES cxd_cmd(id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        INSERT INTO cxd_cmd (name, cmd, dir) VALUES ('test', 'sh', '');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (1, '-c');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (1, 'echo $CXD_TEST');
+        INSERT INTO cxd_env (cmd_id, key, value) VALUES (1, 'CXD_TEST', 'migrated');
+    "#;
+
+fn user_version(path: &std::path::Path) -> anyhow::Result<i64> {
+    let c = rusqlite::Connection::open(path)?;
+    Ok(c.query_row("PRAGMA user_version", (), |row| row.get(0))?)
+}
+
+#[test]
+fn fresh_cache() -> anyhow::Result<()> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    let dir = TempCacheDir::new()?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+
+    cmd.arg("--list").assert().success();
+
+    assert!(user_version(&dir.as_ref().join("cxd.cache"))? > 0);
+    // Nothing to back up
+    assert!(!dir.as_ref().join("cxd.cache.v0.bak").exists());
+
+    Ok(())
+}
+
+#[test]
+fn legacy_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust").assert().success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("migrated"));
+
+    assert!(user_version(&dir.as_ref().join("cxd.cache"))? > 0);
+
+    // Backup holds the original cache
+    let backup = dir.as_ref().join("cxd.cache.v0.bak");
+    assert_eq!(user_version(&backup)?, 0);
+    let c = rusqlite::Connection::open(backup)?;
+    let has_scope: bool = c.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('cxd_cmd') WHERE name = 'scope'",
+        (),
+        |row| row.get(0),
+    )?;
+    assert!(!has_scope);
+
+    Ok(())
+}
+
+#[test]
+fn legacy_braces() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute_batch(
+        r#"
+        INSERT INTO cxd_cmd (name, cmd, dir) VALUES ('braces', 'echo', '');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (2, '{print}');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (2, '{name}');
+        INSERT INTO cxd_arg (cmd_id, data) VALUES (2, '{1}');
+    "#,
+    )?;
+    drop(c);
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--trust").assert().success();
+
+    // Commands from before placeholders existed are run as written
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("braces")
+        .arg("extra")
+        .assert()
+        .success()
+        .stdout("{print} {name} {1} extra\n");
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("--list")
+        .arg("braces")
+        .assert()
+        .success()
+        .stdout(contains("  templated: no\n"));
+
+    // New commands are templated
+    let mut cmd4 = Command::cargo_bin("cxd")?;
+    cmd4.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd4.arg("--add")
+        .arg("new")
+        .arg("echo")
+        .arg("{name}")
+        .assert()
+        .success()
+        .stdout(contains("templated").not());
+
+    let mut cmd5 = Command::cargo_bin("cxd")?;
+    cmd5.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd5.arg("new").assert().success().stdout("new\n");
+
+    Ok(())
+}
+
+#[test]
+fn migrate_once() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin("cxd")?;
+        cmd.env("CXD_CACHE_DIR", dir.as_ref());
+        cmd.arg("--list")
+            .assert()
+            .success()
+            .stdout(contains("[1]: test"));
+    }
+    let mut entries = std::fs::read_dir(dir.as_ref())?
+        .map(|e| Ok(e?.file_name().to_string_lossy().into_owned()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    entries.retain(|e| e.ends_with(".bak"));
+    assert_eq!(entries, ["cxd.cache.v0.bak"]);
+
+    Ok(())
+}
+
+#[test]
+fn newer_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.pragma_update(None, "user_version", 9999)?;
+    drop(c);
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .assert()
+        .failure()
+        .stderr(contains("schema version 9999 is from a newer cxd"));
+
+    // Left untouched, including its journal mode
+    assert_eq!(user_version(&dir.as_ref().join("cxd.cache"))?, 9999);
+    assert!(!dir.as_ref().join("cxd.cache.v9999.bak").exists());
+    assert!(!dir.as_ref().join("cxd.cache-wal").exists());
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    let mode: String = c.query_row("PRAGMA journal_mode", (), |row| row.get(0))?;
+    assert_eq!(mode, "delete");
+
+    Ok(())
+}
+
+#[test]
+fn failed_migration() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+    // A leftover table from an interrupted rebuild fails the scope migration
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute("CREATE TABLE cxd_cmd_new (id INTEGER)", ())?;
+    drop(c);
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .assert()
+        .failure()
+        .stderr(contains("already exists").and(contains("sql")));
+
+    // Rolled back
+    assert_eq!(user_version(&dir.as_ref().join("cxd.cache"))?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn arg_positions() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list").assert().success();
+
+    // Backfilled in rowid order
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    let args = c
+        .prepare("SELECT position, data FROM cxd_arg WHERE cmd_id = 1 ORDER BY position")?
+        .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()?;
+    assert_eq!(
+        args,
+        [(0, "-c".to_string()), (1, "echo $CXD_TEST".to_string())]
+    );
+
+    // Positions are unique within a command
+    assert!(c
+        .execute(
+            "INSERT INTO cxd_arg (cmd_id, position, data) VALUES (1, 1, 'dup')",
+            ()
+        )
+        .is_err());
+
+    Ok(())
+}
+
+/// Runs `cxd --list` from `n` processes at once, returning how many failed
+fn list_concurrently(dir: &TempCacheDir, n: usize) -> anyhow::Result<usize> {
+    let children = (0..n)
+        .map(|_| {
+            Command::cargo_bin("cxd")?
+                .env("CXD_CACHE_DIR", dir.as_ref())
+                .arg("--list")
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .map_err(anyhow::Error::from)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut failed = 0;
+    for mut child in children {
+        if !child.wait()?.success() {
+            failed += 1;
+        }
+    }
+    Ok(failed)
+}
+
+#[test]
+fn concurrent_fresh_cache() -> anyhow::Result<()> {
+    for _ in 0..3 {
+        let dir = TempCacheDir::new()?;
+        assert_eq!(list_concurrently(&dir, 12)?, 0);
+        assert!(user_version(&dir.as_ref().join("cxd.cache"))? > 0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_legacy_cache() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?.execute_batch(LEGACY_CACHE)?;
+
+    assert_eq!(list_concurrently(&dir, 12)?, 0);
+
+    // Only the first to take the lock migrates, so the backup is of the original
+    let backup = dir.as_ref().join("cxd.cache.v0.bak");
+    assert_eq!(user_version(&backup)?, 0);
+
+    Ok(())
+}
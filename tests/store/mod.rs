@@ -0,0 +1,2 @@
+mod migration;
+mod transaction;
@@ -0,0 +1,250 @@
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::{prelude::*, str::contains};
+
+use crate::util::TempCacheDir;
+
+/// Creates the cache, then installs a trigger failing statements on `table` when `condition`
+/// holds, simulating a failure partway through an operation
+fn fail_on(dir: &TempCacheDir, event: &str, table: &str, condition: &str) -> anyhow::Result<()> {
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list").assert().success();
+
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    c.execute_batch(&format!(
+        "CREATE TRIGGER cxd_test_fail BEFORE {event} ON {table} WHEN {condition}
+        BEGIN SELECT RAISE(ABORT, 'simulated failure'); END"
+    ))?;
+    Ok(())
+}
+
+fn count(dir: &TempCacheDir, table: &str) -> anyhow::Result<i64> {
+    let c = rusqlite::Connection::open(dir.as_ref().join("cxd.cache"))?;
+    Ok(
+        c.query_row(&format!("SELECT COUNT(*) FROM {table}"), (), |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+#[test]
+fn insert_arg_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    fail_on(&dir, "INSERT", "cxd_arg", "NEW.data = 'boom'")?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("fine")
+        .arg("boom")
+        .assert()
+        .failure()
+        .stderr(contains("simulated failure"));
+
+    assert_eq!(count(&dir, "cxd_cmd")?, 0);
+    assert_eq!(count(&dir, "cxd_arg")?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn insert_env_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    fail_on(&dir, "INSERT", "cxd_env", "NEW.key = 'BOOM'")?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--add")
+        .arg("--env")
+        .arg("FINE=1")
+        .arg("--env")
+        .arg("BOOM=1")
+        .arg("test")
+        .arg("echo")
+        .arg("fine")
+        .assert()
+        .failure()
+        .stderr(contains("simulated failure"));
+
+    assert_eq!(count(&dir, "cxd_cmd")?, 0);
+    assert_eq!(count(&dir, "cxd_arg")?, 0);
+    assert_eq!(count(&dir, "cxd_env")?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn update_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    fail_on(&dir, "INSERT", "cxd_arg", "NEW.data = 'boom'")?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("test")
+        .arg("echo")
+        .arg("original")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--edit")
+        .arg("test")
+        .arg("--set-cmd")
+        .arg("printf")
+        .arg("--set-args")
+        .arg("fine")
+        .arg("boom")
+        .assert()
+        .failure()
+        .stderr(contains("simulated failure"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test")
+        .assert()
+        .success()
+        .stdout(contains("original"));
+
+    Ok(())
+}
+
+#[test]
+fn delete_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    fail_on(&dir, "DELETE", "cxd_env", "1")?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=kept")
+        .arg("test")
+        .arg("printenv")
+        .arg("CXD_TEST")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--remove")
+        .arg("test")
+        .assert()
+        .failure()
+        .stderr(contains("simulated failure"));
+
+    let mut cmd3 = Command::cargo_bin("cxd")?;
+    cmd3.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd3.arg("test").assert().success().stdout(contains("kept"));
+
+    Ok(())
+}
+
+#[test]
+fn copy_failure() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+    // Fails the copy's link to its tag, after its args and envs were inserted
+    fail_on(&dir, "INSERT", "cxd_cmd_tag", "NEW.cmd_id > 1")?;
+
+    let mut cmd1 = Command::cargo_bin("cxd")?;
+    cmd1.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd1.arg("--add")
+        .arg("--env")
+        .arg("CXD_TEST=1")
+        .arg("--tag")
+        .arg("ci")
+        .arg("test")
+        .arg("echo")
+        .arg("original")
+        .assert()
+        .success();
+
+    let mut cmd2 = Command::cargo_bin("cxd")?;
+    cmd2.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd2.arg("--copy")
+        .arg("test")
+        .arg("test2")
+        .assert()
+        .failure()
+        .stderr(contains("simulated failure"));
+
+    assert_eq!(count(&dir, "cxd_cmd")?, 1);
+    assert_eq!(count(&dir, "cxd_arg")?, 1);
+    assert_eq!(count(&dir, "cxd_env")?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_fresh_writers() -> anyhow::Result<()> {
+    // Unlike `concurrent_writers`, the cache is first created by the writers themselves
+    for _ in 0..3 {
+        let dir = TempCacheDir::new()?;
+        let children = (0..12)
+            .map(|i| {
+                Command::cargo_bin("cxd")?
+                    .env("CXD_CACHE_DIR", dir.as_ref())
+                    .arg("--add")
+                    .arg(format!("test{i}"))
+                    .arg("echo")
+                    .arg("one")
+                    .stdout(std::process::Stdio::null())
+                    .spawn()
+                    .map_err(anyhow::Error::from)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for mut child in children {
+            assert!(child.wait()?.success());
+        }
+
+        assert_eq!(count(&dir, "cxd_cmd")?, 12);
+        assert_eq!(count(&dir, "cxd_arg")?, 12);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_writers() -> anyhow::Result<()> {
+    let dir = TempCacheDir::new()?;
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list").assert().success();
+
+    let children = (0..16)
+        .map(|i| {
+            Command::cargo_bin("cxd")?
+                .env("CXD_CACHE_DIR", dir.as_ref())
+                .arg("--add")
+                .arg(format!("test{i}"))
+                .arg("echo")
+                .arg("one")
+                .arg("two")
+                .arg("three")
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .map_err(anyhow::Error::from)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for mut child in children {
+        assert!(child.wait()?.success());
+    }
+
+    assert_eq!(count(&dir, "cxd_cmd")?, 16);
+    assert_eq!(count(&dir, "cxd_arg")?, 48);
+
+    let mut cmd = Command::cargo_bin("cxd")?;
+    cmd.env("CXD_CACHE_DIR", dir.as_ref());
+    cmd.arg("--list")
+        .assert()
+        .success()
+        .stdout(contains("test0").and(contains("test15")));
+
+    Ok(())
+}
@@ -21,7 +21,6 @@ impl AsRef<Path> for TempCacheDir {
 
 impl Drop for TempCacheDir {
     fn drop(&mut self) {
-        let _ = std::fs::remove_file(&self.0.join("cxd.cache"));
-        std::fs::remove_dir(&self.0).expect("Unique cache dir not present");
+        std::fs::remove_dir_all(&self.0).expect("Unique cache dir not present");
     }
 }